@@ -1,15 +1,38 @@
 use bevy::{
-    prelude::{App, ClearColor, Color, States},
+    log::{Level, LogPlugin},
+    prelude::{
+        default, App, ClearColor, Color, Component, PluginGroup, Res, Resource, State, States,
+    },
     DefaultPlugins,
 };
 use bevy_kira_audio::AudioPlugin;
 use bevy_mod_gizmos::GizmosPlugin;
 
 use crate::{
-    collision::CollisionPlugin, config::ConfigPlugin, input::InputPlugin,
-    kinematics::KinematicsPlugin, loading::LoadingPlugin, meteor::MeteorPlugin,
-    player::PlayerPlugin, projectile::ProjectilePlugin, ship::ShipPlugin, sounds::SoundsPlugin,
-    splash::SplashPlugin, viewport::ViewportPlugin,
+    collision::{CollisionDebugPlugin, CollisionPlugin},
+    config::ConfigPlugin,
+    enemy::EnemyPlugin,
+    game_over::GameOverPlugin,
+    hud::HudPlugin,
+    hyperspace::HyperspacePlugin,
+    input::InputPlugin,
+    kinematics::KinematicsPlugin,
+    loading::{LoadingPlugin, CONFIG_ASSET_PATH},
+    main_menu::MainMenuPlugin,
+    meteor::MeteorPlugin,
+    particle::ParticlePlugin,
+    pause::PausePlugin,
+    player::PlayerPlugin,
+    powerup::PowerupPlugin,
+    projectile::ProjectilePlugin,
+    rng::RngPlugin,
+    score::ScorePlugin,
+    ship::ShipPlugin,
+    sounds::SoundsPlugin,
+    splash::SplashPlugin,
+    viewport::ViewportPlugin,
+    wave::WavePlugin,
+    weapon::WeaponPlugin,
 };
 
 /// Application State.  during development, not all of these will be implemented yet.
@@ -24,38 +47,110 @@ pub enum AppState {
     /// Load the game's assets
     /// Unimplemented
     Loading,
-    /// Show the player controls and maybe let them select their ship
-    /// Unimplemented
+    /// Show the player controls and let them select their ship
     MainMenu,
     /// Play asteroids
     InGame,
-    /// pause the game
-    /// Unimplemented
+    /// Show the final results, and let the player start a new game
+    GameOver,
+    /// Freeze the game, and show the pause menu
     Paused,
 }
 
-pub fn run() {
+/// Entities with this component are despawned at the end of a game.
+#[derive(Debug, Clone, Copy, Default, Component)]
+pub struct InGameCleanup;
+
+/// Set while switching from `Paused` back to `InGame`, so that `OnEnter(AppState::InGame)`
+/// systems can tell a resumed game from a new one.
+#[derive(Debug, Default, Resource)]
+pub struct Resuming(pub bool);
+
+/// Run condition for systems that set up a new game when entering `AppState::InGame`
+pub fn starting_new_game(resuming: Option<Res<Resuming>>) -> bool {
+    !resuming.is_some_and(|resuming| resuming.0)
+}
+
+/// Run condition for systems that keep running while the game is paused
+pub fn game_in_progress(state: Res<State<AppState>>) -> bool {
+    matches!(state.0, AppState::InGame | AppState::Paused)
+}
+
+/// Run condition for systems that tear down a game when leaving `InGame` or `Paused`, which
+/// shouldn't run when the game is only paused or resumed.  The state has already changed by the
+/// time `OnExit` systems run.
+pub fn ending_game(state: Res<State<AppState>>) -> bool {
+    !game_in_progress(state)
+}
+
+/// Options chosen when the app is launched.  The wasm build always uses the defaults, while the
+/// native binary fills them in from the command line.
+#[derive(Debug, Clone, Resource)]
+pub struct LaunchOptions {
+    /// Path to the config asset, relative to the assets folder
+    pub config_path: String,
+    /// Window resolution that takes precedence over the configured one
+    pub resolution: Option<[f32; 2]>,
+    /// Seed for the game's random number generator
+    pub seed: Option<u64>,
+    /// Wave that a new game starts on
+    pub start_wave: u32,
+    /// Minimum level of log messages to show
+    pub log_level: Level,
+}
+
+impl Default for LaunchOptions {
+    fn default() -> Self {
+        Self {
+            config_path: String::from(CONFIG_ASSET_PATH),
+            resolution: None,
+            seed: None,
+            start_wave: 1,
+            log_level: Level::INFO,
+        }
+    }
+}
+
+pub fn run(options: LaunchOptions) {
     let clear_color = Color::hex("080811").unwrap();
+    let log_plugin = LogPlugin {
+        level: options.log_level,
+        ..default()
+    };
     // run app
     App::new()
         .add_state::<AppState>()
-        .add_plugins(DefaultPlugins)
+        .insert_resource(options)
+        .add_plugins(DefaultPlugins.set(log_plugin))
         .add_plugin(AudioPlugin)
         .add_plugin(GizmosPlugin)
         // .add_plugin(WorldInspectorPlugin::new())
         // Game Plugins
         .add_plugin(CollisionPlugin)
+        .add_plugin(CollisionDebugPlugin)
         .add_plugin(ConfigPlugin)
+        .add_plugin(EnemyPlugin)
+        .add_plugin(GameOverPlugin)
+        .add_plugin(HudPlugin)
+        .add_plugin(HyperspacePlugin)
         .add_plugin(InputPlugin)
         .add_plugin(KinematicsPlugin)
         .add_plugin(LoadingPlugin)
+        .add_plugin(MainMenuPlugin)
         .add_plugin(MeteorPlugin)
+        .add_plugin(ParticlePlugin)
+        .add_plugin(PausePlugin)
         .add_plugin(PlayerPlugin)
+        .add_plugin(PowerupPlugin)
         .add_plugin(ProjectilePlugin)
+        .add_plugin(RngPlugin)
+        .add_plugin(ScorePlugin)
         .add_plugin(ShipPlugin)
         .add_plugin(SoundsPlugin)
         .add_plugin(SplashPlugin)
         .add_plugin(ViewportPlugin)
+        .add_plugin(WavePlugin)
+        .add_plugin(WeaponPlugin)
         .insert_resource(ClearColor(clear_color))
         .run();
 }
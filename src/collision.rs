@@ -1,45 +1,428 @@
-//! Really simple collision: everything is a circle under the hood.
+//! Simple collision between circles, convex polygons, capsules and compounds of them.  The
+//! shapes themselves live in the `shape` module.
+//!
+//! Bullets and fast moving colliders are swept along their velocity over the tick, so that they
+//! can't pass through other colliders between ticks.
+//!
+//! Viewport bounded entities live on a torus, so collisions between them are tested across the
+//! viewport edges as well.
+//!
+//! Colliders are sorted into a uniform grid over the viewport bounds once per tick, so that
+//! collision queries only need to look at nearby colliders instead of every pair.  Overlaps are
+//! then detected once per tick, reported through `CollisionStarted` events and kept in the
+//! `Contacts` resource.  Gameplay modules only need to handle the pairings they care about.
+//!
+//! Pairs are always visited in entity order, so that seeded games replay the same way.
+
+use std::collections::BTreeSet;
 
 use bevy::{
-    prelude::{Color, Component, Plugin, Query, Transform},
+    math::Vec3Swizzles,
+    prelude::{
+        in_state, Color, Component, CoreSchedule, Entity, EventWriter, IntoSystemAppConfigs,
+        IntoSystemConfigs, Plugin, Query, Rect, Res, ResMut, Resource, SystemSet, Transform, Vec2,
+    },
     reflect::Reflect,
+    time::fixed_timestep::FixedTime,
 };
 use bevy_mod_gizmos::{draw_gizmo, Gizmo};
 use serde::Deserialize;
 
+use crate::{
+    app::AppState,
+    kinematics::{KinematicsSet, Velocity},
+    shape::{rotation_of, ColliderShape, Contact, Hull},
+    viewport::{system_update_viewport_bounded, ViewportBounded, ViewportBounds},
+};
+
 pub struct CollisionPlugin;
 
 impl Plugin for CollisionPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
-        app.add_system(Self::system_draw_debug);
+        app.register_type::<Collider>();
+        app.register_type::<Bullet>();
+        app.add_event::<CollisionStarted>();
+        app.init_resource::<CollisionConfig>();
+        app.init_resource::<SpatialGrid>();
+        app.init_resource::<Contacts>();
+        app.add_systems(
+            (Self::system_update_grid, Self::system_detect_collisions)
+                .chain()
+                .in_set(CollisionSet)
+                .after(KinematicsSet)
+                .after(system_update_viewport_bounded)
+                .distributive_run_if(in_state(AppState::InGame))
+                .in_schedule(CoreSchedule::FixedUpdate),
+        );
     }
 }
 
+/// Systems that detect collisions, in the fixed update schedule.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemSet)]
+pub struct CollisionSet;
+
 impl CollisionPlugin {
+    fn system_update_grid(
+        mut grid: ResMut<SpatialGrid>,
+        q: Query<(Entity, &Transform, SweptCollider)>,
+        bounds: Res<ViewportBounds>,
+        config: Res<CollisionConfig>,
+        fixed_time: Res<FixedTime>,
+    ) {
+        let dt = fixed_time.period.as_secs_f32();
+        grid.rebuild(
+            bounds.0,
+            config.grid_cell_size,
+            q.iter()
+                // colliders without layers can't collide with anything
+                .filter(|(_, _, (collider, _, _))| !collider.layers.is_empty())
+                .map(|(e, xform, (collider, vel, bullet))| {
+                    let radius = collider.shape.bounding_radius();
+                    let pos = xform.translation.xy();
+                    match sweep_motion(radius, vel, bullet, dt) {
+                        // cover the whole path since the last tick
+                        Some(motion) => (e, pos - motion / 2., radius + motion.length() / 2.),
+                        None => (e, pos, radius),
+                    }
+                }),
+        );
+    }
+
+    fn system_detect_collisions(
+        grid: Res<SpatialGrid>,
+        q: Query<(&Transform, Option<&ViewportBounded>, SweptCollider)>,
+        bounds: Res<ViewportBounds>,
+        fixed_time: Res<FixedTime>,
+        mut contacts: ResMut<Contacts>,
+        mut evw_started: EventWriter<CollisionStarted>,
+    ) {
+        let dt = fixed_time.period.as_secs_f32();
+        let current: BTreeSet<(Entity, Entity)> = grid
+            .candidate_pairs()
+            .into_iter()
+            .filter(|(a, b)| {
+                let Ok([item_a, item_b]) = q.get_many([*a, *b]) else {
+                    return false;
+                };
+                let (xform_a, bounded_a, (collider_a, vel_a, bullet_a)) = item_a;
+                let (xform_b, bounded_b, (collider_b, vel_b, bullet_b)) = item_b;
+                if !collider_a.interacts_with(collider_b) {
+                    return false;
+                }
+                // only entities that both wrap around can touch across the viewport edges
+                let wrap = (bounded_a.is_some() && bounded_b.is_some()).then_some(&*bounds);
+                // sweep along the relative motion if either of them should be swept
+                let swept_a = sweep_motion(collider_a.shape.bounding_radius(), vel_a, bullet_a, dt);
+                let swept_b = sweep_motion(collider_b.shape.bounding_radius(), vel_b, bullet_b, dt);
+                let motion = if swept_a.is_some() || swept_b.is_some() {
+                    let vel = |v: Option<&Velocity>| v.map(|v| v.0).unwrap_or(Vec2::ZERO);
+                    (vel(vel_a) - vel(vel_b)) * dt
+                } else {
+                    Vec2::ZERO
+                };
+                Collider::is_collision((xform_a, collider_a), (xform_b, collider_b), motion, wrap)
+            })
+            .collect();
+
+        for pair in current.difference(&contacts.0) {
+            evw_started.send(CollisionStarted(pair.0, pair.1));
+        }
+        contacts.0 = current;
+    }
+}
+
+/// Draws colliders for debugging.  This is kept separate from the `CollisionPlugin`, since it
+/// needs a renderer.
+pub struct CollisionDebugPlugin;
+
+impl Plugin for CollisionDebugPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.add_system(Self::system_draw_debug);
+    }
+}
+
+impl CollisionDebugPlugin {
     // TODO: toggle based on resource
     fn system_draw_debug(q: Query<(&Collider, &Transform)>) {
         q.for_each(|(collider, xform)| {
             draw_gizmo(Gizmo::new(
                 xform.translation,
-                collider.radius,
+                collider.shape.bounding_radius(),
                 Color::PURPLE,
             ))
         });
     }
 }
 
+#[derive(Debug, Clone, Deserialize, Resource)]
+pub struct CollisionConfig {
+    /// Side length of the spatial grid cells.  Ideally a bit larger than most colliders.
+    pub grid_cell_size: f32,
+}
+
+impl Default for CollisionConfig {
+    fn default() -> Self {
+        Self {
+            grid_cell_size: 128.,
+        }
+    }
+}
+
+/// Sent when two colliders start overlapping.  The order of the entities is arbitrary.
+#[derive(Debug, Clone, Copy)]
+pub struct CollisionStarted(pub Entity, pub Entity);
+
+impl CollisionStarted {
+    /// The colliding entities, ordered so that the first one passes `is_first`.
+    pub fn ordered(&self, is_first: impl Fn(Entity) -> bool) -> Option<(Entity, Entity)> {
+        if is_first(self.0) {
+            Some((self.0, self.1))
+        } else if is_first(self.1) {
+            Some((self.1, self.0))
+        } else {
+            None
+        }
+    }
+}
+
+/// Marks colliders that should always be swept along their velocity, like projectiles.  Other
+/// colliders are only swept when they move further than their own size in a tick.
+#[derive(Debug, Clone, Copy, Default, Component, Reflect)]
+pub struct Bullet;
+
+/// A collider, along with what decides whether it gets swept
+type SweptCollider<'a> = (&'a Collider, Option<&'a Velocity>, Option<&'a Bullet>);
+
+/// How far a collider moved in the last tick, if it should be swept along its path.
+fn sweep_motion(
+    bounding_radius: f32,
+    vel: Option<&Velocity>,
+    bullet: Option<&Bullet>,
+    dt: f32,
+) -> Option<Vec2> {
+    let motion = vel?.0 * dt;
+    let fast = motion.length() > bounding_radius;
+    (motion != Vec2::ZERO && (fast || bullet.is_some())).then_some(motion)
+}
+
+/// Pairs of entities that are currently overlapping, as of the latest tick, in entity order.
+#[derive(Debug, Default, Resource)]
+pub struct Contacts(BTreeSet<(Entity, Entity)>);
+
+impl Contacts {
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, Entity)> + '_ {
+        self.0.iter().copied()
+    }
+}
+
+/// Named groups of colliders, used to decide which colliders can collide.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub enum CollisionLayer {
+    Player,
+    Meteor,
+    Projectile,
+    Enemy,
+    EnemyProjectile,
+    Powerup,
+}
+
+/// A set of collision layers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Reflect)]
+#[serde(from = "Vec<CollisionLayer>")]
+pub struct CollisionLayers(u32);
+
+impl CollisionLayers {
+    pub fn intersects(&self, other: CollisionLayers) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Layers in either set
+    pub fn union(self, other: CollisionLayers) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    /// Layers in this set, but not in `other`
+    pub fn difference(self, other: CollisionLayers) -> Self {
+        Self(self.0 & !other.0)
+    }
+}
+
+impl From<CollisionLayer> for CollisionLayers {
+    fn from(layer: CollisionLayer) -> Self {
+        Self(1 << layer as u32)
+    }
+}
+
+impl From<Vec<CollisionLayer>> for CollisionLayers {
+    fn from(layers: Vec<CollisionLayer>) -> Self {
+        Self(layers.into_iter().fold(0, |acc, l| acc | Self::from(l).0))
+    }
+}
+
 #[derive(Debug, Clone, Default, Component, Deserialize, Reflect)]
 pub struct Collider {
-    pub radius: f32,
+    /// shape relative to the entity, which rotates with its transform
+    pub shape: ColliderShape,
+    /// layers that this collider is in
+    pub layers: CollisionLayers,
+    /// layers that this collider collides with
+    pub mask: CollisionLayers,
 }
 
 impl Collider {
+    /// Two colliders interact if each one's mask includes a layer of the other.
+    pub fn interacts_with(&self, other: &Collider) -> bool {
+        self.mask.intersects(other.layers) && other.mask.intersects(self.layers)
+    }
+
+    /// Test whether two colliders overlapped at any point during the last tick, where `motion`
+    /// is how far entity1 moved relative to entity2 during the tick (zero for a static test).
+    /// Rotation during the tick is ignored.  With `wrap` bounds, the distance between them is
+    /// measured on the torus defined by the bounds.
     pub fn is_collision(
         entity1: (&Transform, &Collider),
         entity2: (&Transform, &Collider),
+        motion: Vec2,
+        wrap: Option<&ViewportBounds>,
     ) -> bool {
-        let dist = entity1.0.translation.distance(entity2.0.translation);
-        let min_dist = entity1.1.radius + entity2.1.radius;
-        dist <= min_dist
+        let (mut hulls1, hulls2) = Self::relative_hulls(entity1, entity2, wrap);
+        if motion != Vec2::ZERO {
+            hulls1 = hulls1.iter().map(|hull| hull.swept(motion)).collect();
+        }
+        hulls1
+            .iter()
+            .any(|hull1| hulls2.iter().any(|hull2| hull1.intersects(hull2)))
+    }
+
+    /// The deepest contact between two overlapping colliders, relative to entity1's position.
+    /// The normal points from entity1 towards entity2.
+    pub fn contact(
+        entity1: (&Transform, &Collider),
+        entity2: (&Transform, &Collider),
+        wrap: Option<&ViewportBounds>,
+    ) -> Option<Contact> {
+        let (hulls1, hulls2) = Self::relative_hulls(entity1, entity2, wrap);
+        hulls1
+            .iter()
+            .flat_map(|hull1| hulls2.iter().filter_map(|hull2| hull1.contact(hull2)))
+            .max_by(|a, b| a.depth.total_cmp(&b.depth))
+    }
+
+    /// The hulls of both colliders, placed relative to entity1 so that wrapping only moves
+    /// entity2.
+    fn relative_hulls(
+        entity1: (&Transform, &Collider),
+        entity2: (&Transform, &Collider),
+        wrap: Option<&ViewportBounds>,
+    ) -> (Vec<Hull>, Vec<Hull>) {
+        let mut delta = entity2.0.translation.xy() - entity1.0.translation.xy();
+        if let Some(bounds) = wrap {
+            delta = bounds.wrap_delta(delta);
+        }
+        (
+            entity1.1.shape.hulls(Vec2::ZERO, rotation_of(entity1.0)),
+            entity2.1.shape.hulls(delta, rotation_of(entity2.0)),
+        )
+    }
+
+    pub fn contains_point(&self, xform: &Transform, point: Vec2) -> bool {
+        self.shape
+            .hulls(xform.translation.xy(), rotation_of(xform))
+            .iter()
+            .any(|hull| hull.contains_point(point))
+    }
+}
+
+/// A uniform grid of cells over the viewport bounds, each listing the colliders that overlap it.
+/// The grid wraps around at the edges like the viewport does, so colliders near one edge are also
+/// listed in the cells at the opposite edge.
+#[derive(Debug, Default, Resource)]
+pub struct SpatialGrid {
+    bounds: Rect,
+    cell_size: Vec2,
+    columns: usize,
+    rows: usize,
+    cells: Vec<Vec<Entity>>,
+}
+
+impl SpatialGrid {
+    /// Clear the grid and sort the given colliders into it, as (entity, position, radius).
+    /// Cells are at least `cell_size` wide, so that they evenly divide the bounds.
+    pub fn rebuild(
+        &mut self,
+        bounds: Rect,
+        cell_size: f32,
+        colliders: impl Iterator<Item = (Entity, Vec2, f32)>,
+    ) {
+        let size = bounds.size();
+        let cell_size = cell_size.max(1.);
+        self.bounds = bounds;
+        self.columns = ((size.x / cell_size).floor() as usize).max(1);
+        self.rows = ((size.y / cell_size).floor() as usize).max(1);
+        self.cell_size = size / Vec2::new(self.columns as f32, self.rows as f32);
+        let cell_count = self.columns * self.rows;
+        self.cells.iter_mut().for_each(|cell| cell.clear());
+        self.cells.resize_with(cell_count, Vec::new);
+
+        for (entity, pos, radius) in colliders {
+            let indices: Vec<usize> = self.cell_indices(pos, radius).collect();
+            for idx in indices {
+                self.cells[idx].push(entity);
+            }
+        }
+    }
+
+    /// Entities whose cells overlap the circle at `pos` with `radius`.  These are only candidates,
+    /// so they still need a proper collision test.
+    pub fn query(&self, pos: Vec2, radius: f32) -> Vec<Entity> {
+        if self.cells.is_empty() {
+            return Vec::new();
+        }
+        let mut found: Vec<Entity> = self
+            .cell_indices(pos, radius)
+            .flat_map(|idx| self.cells[idx].iter().copied())
+            .collect();
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+
+    /// Every pair of entities that share at least one cell, each listed once, in entity order.
+    pub fn candidate_pairs(&self) -> Vec<(Entity, Entity)> {
+        let mut pairs: Vec<(Entity, Entity)> = Vec::new();
+        for cell in self.cells.iter() {
+            for (i, a) in cell.iter().enumerate() {
+                for b in cell[i + 1..].iter() {
+                    if a == b {
+                        continue;
+                    }
+                    pairs.push((*a.min(b), *a.max(b)));
+                }
+            }
+        }
+        pairs.sort_unstable();
+        pairs.dedup();
+        pairs
+    }
+
+    /// Indices of the cells covered by a circle, wrapping around the edges of the grid.
+    fn cell_indices(&self, pos: Vec2, radius: f32) -> impl Iterator<Item = usize> + '_ {
+        let min = ((pos - radius - self.bounds.min) / self.cell_size).floor();
+        let max = ((pos + radius - self.bounds.min) / self.cell_size).floor();
+        // a circle larger than the grid covers every cell once
+        let columns = ((max.x - min.x) as usize + 1).min(self.columns);
+        let rows = ((max.y - min.y) as usize + 1).min(self.rows);
+        let (min_col, min_row) = (min.x as i64, min.y as i64);
+        (0..rows).flat_map(move |row| {
+            let row = (min_row + row as i64).rem_euclid(self.rows as i64) as usize;
+            (0..columns).map(move |col| {
+                let col = (min_col + col as i64).rem_euclid(self.columns as i64) as usize;
+                row * self.columns + col
+            })
+        })
     }
 }
@@ -2,15 +2,17 @@ use std::str::from_utf8;
 
 use bevy::{
     asset::{AssetLoader, LoadContext, LoadedAsset},
-    prelude::{info, AddAsset, Commands, DetectChanges, Plugin, Res, Resource},
+    prelude::{info, warn, AddAsset, Commands, DetectChanges, Plugin, Res, Resource},
     reflect::TypeUuid,
     utils::BoxedFuture,
 };
 use serde::Deserialize;
 
 use crate::{
-    loading::AssetsConfig, meteor::MeteorsConfig, player::PlayerConfig,
-    projectile::ProjectileConfig, viewport::ViewportConfig,
+    app::LaunchOptions, collision::CollisionConfig, enemy::EnemiesConfig, loading::AssetsConfig,
+    main_menu::ShipSelection, meteor::MeteorsConfig, particle::ParticlesConfig,
+    player::PlayerConfig, powerup::PowerupsConfig, rng::RngConfig, score::ScoreConfig,
+    viewport::ViewportConfig, wave::WaveConfig, weapon::WeaponsConfig,
 };
 
 pub struct ConfigPlugin;
@@ -30,11 +32,26 @@ pub struct Config {
     pub player: PlayerConfig,
     pub viewport: ViewportConfig,
     pub meteors: MeteorsConfig,
-    pub projectile: ProjectileConfig,
+    pub weapons: WeaponsConfig,
+    pub enemies: EnemiesConfig,
+    pub powerups: PowerupsConfig,
+    pub particles: ParticlesConfig,
+    pub waves: WaveConfig,
+    #[serde(default)]
+    pub rng: RngConfig,
+    #[serde(default)]
+    pub collision: CollisionConfig,
+    #[serde(default)]
+    pub score: ScoreConfig,
 }
 
 impl Config {
-    pub fn system_handle_config_change(mut commands: Commands, opt_config: Option<Res<Config>>) {
+    pub fn system_handle_config_change(
+        mut commands: Commands,
+        opt_config: Option<Res<Config>>,
+        opt_options: Option<Res<LaunchOptions>>,
+        opt_ship_selection: Option<Res<ShipSelection>>,
+    ) {
         if opt_config.is_none() {
             return;
         }
@@ -47,10 +64,33 @@ impl Config {
 
         // insert all resources derived from config
         commands.insert_resource(config.assets.clone());
-        commands.insert_resource(config.viewport.clone());
-        commands.insert_resource(config.player.clone());
+        let mut viewport = config.viewport.clone();
+        // launch options take precedence over the config file
+        if let Some(resolution) = opt_options.and_then(|options| options.resolution) {
+            viewport.resolution = resolution;
+        }
+        commands.insert_resource(viewport);
+        let mut player = config.player.clone();
+        // so does the ship picked in the main menu
+        if let Some(ship_selection) = opt_ship_selection {
+            player.ship.sprite_id = ship_selection.0.clone();
+        }
+        // weapons are looked up by id every frame, so unknown ones are only reported here
+        for weapon_id in [&player.ship.weapon, config.powerups.spread_shot_weapon()] {
+            if !config.weapons.contains(weapon_id) {
+                warn!("Unknown weapon: {}", weapon_id);
+            }
+        }
+        commands.insert_resource(player);
         commands.insert_resource(config.meteors.clone());
-        commands.insert_resource(config.projectile.clone());
+        commands.insert_resource(config.weapons.clone());
+        commands.insert_resource(config.enemies.clone());
+        commands.insert_resource(config.powerups.clone());
+        commands.insert_resource(config.particles.clone());
+        commands.insert_resource(config.rng.clone());
+        commands.insert_resource(config.collision.clone());
+        commands.insert_resource(config.score.clone());
+        commands.insert_resource(config.waves.clone());
     }
 }
 
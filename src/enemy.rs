@@ -0,0 +1,385 @@
+//! Enemy saucers, which fly across the screen and shoot at the player.
+//!
+//! As in the original game there are large saucers, which shoot wildly, and small ones, which aim
+//! better as the score goes up.  Only one saucer is around at a time.
+
+use std::f32::consts::PI;
+
+use bevy::{
+    ecs::system::SystemParam,
+    math::Vec3Swizzles,
+    prelude::{
+        Bundle, Color, Commands, Component, Entity, EventReader, EventWriter, Image,
+        IntoSystemAppConfig, IntoSystemConfig, OnEnter, OnUpdate, Plugin, Quat, Query, Res, ResMut,
+        Resource, Transform, Vec2, Vec3, With,
+    },
+    sprite::{Sprite, SpriteBundle},
+    time::{Time, Timer, TimerMode},
+    utils::{default, HashSet},
+};
+use rand::{distributions::Uniform, Rng};
+use serde::Deserialize;
+
+use crate::{
+    app::{starting_new_game, AppState, InGameCleanup},
+    collision::{Collider, CollisionLayers, CollisionStarted},
+    kinematics::{KinematicsBundle, Velocity},
+    loading::AssetMap,
+    particle::Explosions,
+    player::{PlayerDied, PlayerMarker},
+    projectile::{ProjectileBundle, ProjectileComponent, ProjectileConfig},
+    rng::GameRng,
+    score::{PointsScored, Score},
+    shape::ColliderShape,
+    ship::Shield,
+    sounds::SoundEvent,
+    viewport::{ViewportBounded, ViewportBounds},
+};
+
+#[derive(Debug)]
+pub struct EnemyPlugin;
+
+impl Plugin for EnemyPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.add_system(
+            SaucerSpawner::system_reset
+                .run_if(starting_new_game)
+                .in_schedule(OnEnter(AppState::InGame)),
+        );
+        app.add_system(SaucerSpawner::system_spawn.in_set(OnUpdate(AppState::InGame)));
+        app.add_system(SaucerBundle::system_fly.in_set(OnUpdate(AppState::InGame)));
+        app.add_system(SaucerBundle::system_shoot.in_set(OnUpdate(AppState::InGame)));
+        app.add_system(
+            SaucerBundle::system_handle_projectile_collision.in_set(OnUpdate(AppState::InGame)),
+        );
+        app.add_system(
+            SaucerBundle::system_handle_player_collision.in_set(OnUpdate(AppState::InGame)),
+        );
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Resource)]
+pub struct EnemiesConfig {
+    /// seconds between one saucer leaving and the next one showing up
+    spawn_interval: f32,
+    /// chance of a saucer being small, from 0 to 1
+    small_chance: f32,
+    /// seconds between zig-zags
+    zigzag_interval: f32,
+    collision_layers: CollisionLayers,
+    collision_mask: CollisionLayers,
+    large: SaucerConfig,
+    small: SaucerConfig,
+    /// projectiles fired by saucers
+    projectile: ProjectileConfig,
+}
+
+/// Configuration for a saucer of a particular size.
+/// Collision shapes are in sprite pixels, before scaling.
+#[derive(Debug, Clone, Deserialize)]
+struct SaucerConfig {
+    sprite_id: String,
+    /// hex color to tint the sprite with
+    color: String,
+    scale: f32,
+    collision_shape: ColliderShape,
+    speed: f32,
+    /// seconds between shots
+    shoot_interval: f32,
+    /// largest angle in radians that shots can miss the player by
+    aim_error: f32,
+    /// score at which the saucer stops missing, with the error shrinking steadily until then
+    perfect_aim_score: Option<u32>,
+    /// points for destroying the saucer
+    points: u32,
+}
+
+impl SaucerConfig {
+    fn aim_error_at(&self, score: u32) -> f32 {
+        match self.perfect_aim_score {
+            Some(perfect) if perfect > 0 => {
+                self.aim_error * (1. - score as f32 / perfect as f32).max(0.)
+            }
+            Some(_) => 0.,
+            None => self.aim_error,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SaucerSize {
+    Large,
+    Small,
+}
+
+impl EnemiesConfig {
+    fn saucer(&self, size: SaucerSize) -> &SaucerConfig {
+        match size {
+            SaucerSize::Large => &self.large,
+            SaucerSize::Small => &self.small,
+        }
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct SaucerBehavior {
+    size: SaucerSize,
+    /// horizontal direction of travel, either 1 or -1
+    heading: f32,
+    /// time left until the saucer has crossed the screen
+    lifetime: Timer,
+    zigzag: Timer,
+    shoot: Timer,
+}
+
+/// What saucers need to enter the screen and shoot
+#[derive(SystemParam)]
+struct SaucerContext<'w> {
+    config: Res<'w, EnemiesConfig>,
+    bounds: Res<'w, ViewportBounds>,
+    images: Res<'w, AssetMap<Image>>,
+    rng: ResMut<'w, GameRng>,
+}
+
+/// Counts down to the next saucer while there is none.
+#[derive(Debug, Resource)]
+struct SaucerSpawner(Timer);
+
+impl SaucerSpawner {
+    fn system_reset(mut commands: Commands, config: Res<EnemiesConfig>) {
+        commands.insert_resource(SaucerSpawner(Timer::from_seconds(
+            config.spawn_interval,
+            TimerMode::Once,
+        )));
+    }
+
+    fn system_spawn(
+        mut commands: Commands,
+        spawner: Option<ResMut<SaucerSpawner>>,
+        q_saucers: Query<(), With<SaucerBehavior>>,
+        time: Res<Time>,
+        mut context: SaucerContext,
+    ) {
+        let Some(mut spawner) = spawner else {
+            return;
+        };
+        if !q_saucers.is_empty() || !spawner.0.tick(time.delta()).finished() {
+            return;
+        }
+        spawner.0.reset();
+        let rng = &mut context.rng.rng;
+        let size = if rng.gen::<f32>() < context.config.small_chance {
+            SaucerSize::Small
+        } else {
+            SaucerSize::Large
+        };
+        commands.spawn(SaucerBundle::new_random(
+            rng,
+            size,
+            &context.config,
+            &context.bounds,
+            &context.images,
+        ));
+    }
+}
+
+#[derive(Bundle)]
+pub struct SaucerBundle {
+    behavior: SaucerBehavior,
+    cleanup: InGameCleanup,
+    collider: Collider,
+    bounded: ViewportBounded,
+    #[bundle]
+    sprite_bundle: SpriteBundle,
+    #[bundle]
+    kinematics: KinematicsBundle,
+}
+
+impl SaucerBundle {
+    /// A saucer entering from a random side of the screen, at a random height
+    fn new_random<R: Rng>(
+        rng: &mut R,
+        size: SaucerSize,
+        config: &EnemiesConfig,
+        bounds: &ViewportBounds,
+        images: &AssetMap<Image>,
+    ) -> Self {
+        let saucer_config = config.saucer(size);
+        let heading = if rng.gen_bool(0.5) { 1. } else { -1. };
+        let x = if heading > 0. {
+            bounds.0.min.x
+        } else {
+            bounds.0.max.x
+        };
+        let y = rng.sample(Uniform::new(bounds.0.min.y, bounds.0.max.y));
+        let crossing_time = bounds.0.width() / saucer_config.speed;
+        Self {
+            behavior: SaucerBehavior {
+                size,
+                heading,
+                lifetime: Timer::from_seconds(crossing_time, TimerMode::Once),
+                zigzag: Timer::from_seconds(config.zigzag_interval, TimerMode::Repeating),
+                shoot: Timer::from_seconds(saucer_config.shoot_interval, TimerMode::Repeating),
+            },
+            cleanup: InGameCleanup,
+            collider: Collider {
+                shape: saucer_config.collision_shape.scaled(saucer_config.scale),
+                layers: config.collision_layers,
+                mask: config.collision_mask,
+            },
+            bounded: ViewportBounded,
+            sprite_bundle: SpriteBundle {
+                sprite: Sprite {
+                    color: Color::hex(&saucer_config.color).unwrap_or(Color::WHITE),
+                    ..default()
+                },
+                texture: images.0[&saucer_config.sprite_id].clone(),
+                transform: Transform {
+                    translation: Vec3::new(x, y, 0.),
+                    scale: Vec3::ONE * saucer_config.scale,
+                    ..default()
+                },
+                ..default()
+            },
+            kinematics: KinematicsBundle {
+                velocity: Velocity(Vec2::X * heading * saucer_config.speed),
+                ..default()
+            },
+        }
+    }
+
+    /// Zig-zag across the screen, and leave once on the other side.
+    fn system_fly(
+        mut commands: Commands,
+        mut q: Query<(Entity, &mut SaucerBehavior, &mut Velocity)>,
+        time: Res<Time>,
+        config: Res<EnemiesConfig>,
+        mut rng: ResMut<GameRng>,
+    ) {
+        for (entity, mut behavior, mut vel) in q.iter_mut() {
+            if behavior.lifetime.tick(time.delta()).finished() {
+                commands.entity(entity).despawn();
+                continue;
+            }
+            if behavior.zigzag.tick(time.delta()).just_finished() {
+                // go straight, or veer diagonally up or down
+                let speed = config.saucer(behavior.size).speed;
+                let veer = rng.rng.sample(Uniform::new_inclusive(-1, 1)) as f32;
+                vel.0 = Vec2::new(behavior.heading, veer) * speed;
+            }
+        }
+    }
+
+    fn system_shoot(
+        mut commands: Commands,
+        mut q_saucers: Query<(&mut SaucerBehavior, &Transform, &Velocity)>,
+        q_player: Query<&Transform, With<PlayerMarker>>,
+        time: Res<Time>,
+        score: Res<Score>,
+        mut context: SaucerContext,
+        mut evw_sounds: EventWriter<SoundEvent>,
+    ) {
+        let SaucerContext {
+            config,
+            bounds,
+            images,
+            rng,
+        } = &mut context;
+        for (mut behavior, xform, vel) in q_saucers.iter_mut() {
+            if !behavior.shoot.tick(time.delta()).just_finished() {
+                continue;
+            }
+            let saucer_config = config.saucer(behavior.size);
+            // aim at the player, the short way around the screen
+            let aim = match q_player.get_single() {
+                Ok(player_xform) => {
+                    let delta = (player_xform.translation - xform.translation).xy();
+                    let error = saucer_config.aim_error_at(score.0);
+                    let error = if error > 0. {
+                        rng.rng.sample(Uniform::new_inclusive(-error, error))
+                    } else {
+                        0.
+                    };
+                    Vec2::Y.angle_between(bounds.wrap_delta(delta)) + error
+                }
+                Err(_) => rng.rng.sample(Uniform::new(0., PI * 2.)),
+            };
+            let shot_xform = Transform::from_translation(xform.translation)
+                .with_rotation(Quat::from_rotation_z(aim));
+            commands.spawn(ProjectileBundle::from_config(
+                &config.projectile,
+                &shot_xform,
+                vel.0,
+                images,
+            ));
+            evw_sounds.send(SoundEvent::new("laser"));
+        }
+    }
+
+    fn system_handle_projectile_collision(
+        mut commands: Commands,
+        mut evr_collisions: EventReader<CollisionStarted>,
+        q_saucers: Query<(&SaucerBehavior, &Transform, &Velocity)>,
+        q_projectile: Query<(), With<ProjectileComponent>>,
+        config: Res<EnemiesConfig>,
+        mut evw_points: EventWriter<PointsScored>,
+        mut explosions: Explosions,
+    ) {
+        // saucer shots can't hit saucers, so these are all the player's
+        let mut destroyed: HashSet<Entity> = HashSet::default();
+        for ev in evr_collisions.iter() {
+            let Some((projectile_entity, saucer_entity)) = ev.ordered(|e| q_projectile.contains(e))
+            else {
+                continue;
+            };
+            let Ok((behavior, xform, vel)) = q_saucers.get(saucer_entity) else {
+                continue;
+            };
+            if destroyed.contains(&projectile_entity) || destroyed.contains(&saucer_entity) {
+                continue;
+            }
+            destroyed.insert(projectile_entity);
+            destroyed.insert(saucer_entity);
+            commands.entity(projectile_entity).despawn();
+            commands.entity(saucer_entity).despawn();
+            evw_points.send(PointsScored(config.saucer(behavior.size).points));
+            explosions.explode(xform.translation, vel.0);
+        }
+    }
+
+    /// Saucers and their shots destroy the player when they hit, unless its shield is up.  They
+    /// are destroyed either way.
+    fn system_handle_player_collision(
+        mut commands: Commands,
+        mut evr_collisions: EventReader<CollisionStarted>,
+        q_saucers: Query<(&Transform, &Velocity), With<SaucerBehavior>>,
+        q_projectile: Query<(), With<ProjectileComponent>>,
+        q_player: Query<&Shield, With<PlayerMarker>>,
+        mut evw_died: EventWriter<PlayerDied>,
+        mut explosions: Explosions,
+    ) {
+        let mut destroyed: HashSet<Entity> = HashSet::default();
+        for ev in evr_collisions.iter() {
+            let Some((player_entity, other_entity)) = ev.ordered(|e| q_player.contains(e)) else {
+                continue;
+            };
+            let saucer = q_saucers.get(other_entity).ok();
+            if saucer.is_none() && !q_projectile.contains(other_entity) {
+                continue;
+            }
+            if !destroyed.insert(other_entity) {
+                continue;
+            }
+            commands.entity(other_entity).despawn();
+            if let Some((xform, vel)) = saucer {
+                explosions.explode(xform.translation, vel.0);
+            }
+            let Ok(shield) = q_player.get(player_entity) else {
+                continue;
+            };
+            if !shield.0 {
+                evw_died.send(PlayerDied(player_entity));
+            }
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use bevy::{
+    prelude::{
+        default, AssetServer, Color, Commands, Component, Entity, IntoSystemAppConfig,
+        IntoSystemConfig, NextState, OnEnter, OnExit, OnUpdate, Plugin, Query, Res, ResMut,
+        Transform, Vec3, With,
+    },
+    text::{Text, Text2dBundle, TextAlignment, TextStyle},
+};
+
+use crate::{
+    app::{ending_game, AppState, InGameCleanup},
+    collision::Collider,
+    input::ClickListener,
+    score::Score,
+    shape::ColliderShape,
+    viewport::ViewportBounds,
+    wave::Wave,
+};
+
+#[derive(Debug)]
+pub struct GameOverPlugin;
+
+impl Plugin for GameOverPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.add_system(
+            Self::system_cleanup_game
+                .run_if(ending_game)
+                .in_schedule(OnExit(AppState::InGame)),
+        );
+        app.add_system(
+            Self::system_cleanup_game
+                .run_if(ending_game)
+                .in_schedule(OnExit(AppState::Paused)),
+        );
+        app.add_system(Self::system_setup.in_schedule(OnEnter(AppState::GameOver)));
+        app.add_system(Self::system_handle_click.in_set(OnUpdate(AppState::GameOver)));
+        app.add_system(Self::system_cleanup.in_schedule(OnExit(AppState::GameOver)));
+    }
+}
+
+impl GameOverPlugin {
+    fn system_cleanup_game(mut commands: Commands, q: Query<Entity, With<InGameCleanup>>) {
+        q.iter().for_each(|e| commands.entity(e).despawn());
+    }
+
+    fn system_setup(
+        mut commands: Commands,
+        asset_server: Res<AssetServer>,
+        viewport_bounds: Res<ViewportBounds>,
+        score: Res<Score>,
+        wave: Res<Wave>,
+    ) {
+        let font = asset_server.load("fira_sans/FiraSans-Regular.ttf");
+
+        // spawn results text
+        let results_text_style = TextStyle {
+            font_size: 96.0,
+            color: Color::WHITE,
+            font: font.clone(),
+        };
+        let results = format!("Game Over\nScore: {}\nWave: {}", score.0, wave.number);
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(results, results_text_style)
+                    .with_alignment(TextAlignment::Center),
+                transform: Transform {
+                    translation: Vec3::new(0., 128., 0.),
+                    ..default()
+                },
+                ..default()
+            },
+            GameOverCleanup,
+        ));
+
+        // spawn prompt text
+        let prompt_text_style = TextStyle {
+            font_size: 64.0,
+            color: Color::WHITE,
+            font,
+        };
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section("Click To Play Again", prompt_text_style),
+                transform: Transform {
+                    translation: Vec3::new(0., -256., 0.),
+                    ..default()
+                },
+                ..default()
+            },
+            GameOverCleanup,
+        ));
+
+        // spawn click listener
+        let radius = viewport_bounds.0.half_size().max_element();
+        commands.spawn((
+            ClickListener::default(),
+            Collider {
+                shape: ColliderShape::Circle { radius },
+                ..default()
+            },
+            Transform {
+                translation: Vec3::new(0., 0., -1.),
+                ..default()
+            },
+            GameOverClickListener,
+            GameOverCleanup,
+        ));
+    }
+
+    fn system_handle_click(
+        mut q: Query<&mut ClickListener, With<GameOverClickListener>>,
+        mut next_state: ResMut<NextState<AppState>>,
+    ) {
+        let events: Vec<_> = q
+            .iter_mut()
+            .flat_map(|mut listener| listener.0.drain().collect::<Vec<_>>())
+            .collect();
+        if !events.is_empty() {
+            next_state.set(AppState::InGame);
+        }
+    }
+
+    fn system_cleanup(mut commands: Commands, q: Query<Entity, With<GameOverCleanup>>) {
+        q.iter().for_each(|e| commands.entity(e).despawn());
+    }
+}
+
+// entities with this component will be cleaned up when leaving the game over
+// screen.
+#[derive(Debug, Component)]
+pub struct GameOverCleanup;
+
+#[derive(Debug, Component)]
+pub struct GameOverClickListener;
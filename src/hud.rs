@@ -0,0 +1,129 @@
+//! Heads up display, showing the score and wave during a game.
+
+use bevy::{
+    prelude::{
+        default, AssetServer, Color, Commands, Component, EventReader, IntoSystemAppConfig,
+        IntoSystemConfig, OnEnter, OnUpdate, Plugin, Query, Res, Transform, Vec3, With,
+    },
+    sprite::Anchor,
+    text::{Text, Text2dBundle, TextStyle},
+};
+
+use crate::{
+    app::{starting_new_game, AppState, InGameCleanup},
+    score::ScoreChanged,
+    viewport::ViewportConfig,
+    wave::{WaveCleared, WaveStarted},
+};
+
+/// Distance between the HUD and the edges of the screen
+const HUD_MARGIN: f32 = 32.;
+
+#[derive(Debug)]
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.add_system(
+            Self::system_setup
+                .run_if(starting_new_game)
+                .in_schedule(OnEnter(AppState::InGame)),
+        );
+        app.add_system(Self::system_update_score.in_set(OnUpdate(AppState::InGame)));
+        app.add_system(Self::system_update_wave.in_set(OnUpdate(AppState::InGame)));
+    }
+}
+
+impl HudPlugin {
+    fn system_setup(
+        mut commands: Commands,
+        asset_server: Res<AssetServer>,
+        viewport_config: Res<ViewportConfig>,
+    ) {
+        let text_style = TextStyle {
+            font_size: 48.0,
+            color: Color::WHITE,
+            font: asset_server.load("fira_sans/FiraSans-Regular.ttf"),
+        };
+        // top corners of the screen, above the game
+        let visible = viewport_config.visible_rect();
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(score_text(0), text_style.clone()),
+                text_anchor: Anchor::TopLeft,
+                transform: Transform {
+                    translation: Vec3::new(
+                        visible.min.x + HUD_MARGIN,
+                        visible.max.y - HUD_MARGIN,
+                        5.,
+                    ),
+                    ..default()
+                },
+                ..default()
+            },
+            HudScoreText,
+            InGameCleanup,
+        ));
+        // filled in once the first wave starts
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section("", text_style),
+                text_anchor: Anchor::TopRight,
+                transform: Transform {
+                    translation: Vec3::new(
+                        visible.max.x - HUD_MARGIN,
+                        visible.max.y - HUD_MARGIN,
+                        5.,
+                    ),
+                    ..default()
+                },
+                ..default()
+            },
+            HudWaveText,
+            InGameCleanup,
+        ));
+    }
+
+    fn system_update_score(
+        mut evr_changed: EventReader<ScoreChanged>,
+        mut q: Query<&mut Text, With<HudScoreText>>,
+    ) {
+        let Some(changed) = evr_changed.iter().last() else {
+            return;
+        };
+        for mut text in q.iter_mut() {
+            text.sections[0].value = score_text(changed.score);
+        }
+    }
+
+    fn system_update_wave(
+        mut evr_started: EventReader<WaveStarted>,
+        mut evr_cleared: EventReader<WaveCleared>,
+        mut q: Query<&mut Text, With<HudWaveText>>,
+    ) {
+        // a wave can't be cleared in the same frame that it starts
+        let started = evr_started.iter().last().map(|ev| format!("Wave {}", ev.0));
+        let cleared = evr_cleared
+            .iter()
+            .last()
+            .map(|ev| format!("Wave {} cleared", ev.0));
+        let Some(value) = cleared.or(started) else {
+            return;
+        };
+        for mut text in q.iter_mut() {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
+
+fn score_text(score: u32) -> String {
+    format!("Score: {}", score)
+}
+
+/// The text showing the current score
+#[derive(Debug, Component)]
+pub struct HudScoreText;
+
+/// The text showing the current wave
+#[derive(Debug, Component)]
+pub struct HudWaveText;
@@ -0,0 +1,163 @@
+//! The player's hyperspace jump.
+//!
+//! Jumping makes the ship vanish for a moment, and reappear at a random place in the viewport.
+//! It can't collide with anything in between, but might blow up on re-entry.
+
+use bevy::{
+    prelude::{
+        Commands, Component, Entity, EventWriter, IntoSystemConfig, OnUpdate, Plugin, Query, Res,
+        ResMut, Transform, Vec2, Vec3, Visibility, With, Without,
+    },
+    time::{Time, Timer, TimerMode},
+};
+use rand::{distributions::Uniform, Rng};
+
+use crate::{
+    app::AppState,
+    collision::{Collider, CollisionLayers},
+    kinematics::{Acceleration, AngularAcceleration, PreviousTransform, Velocity},
+    player::{system_handle_death, PlayerDied, PlayerMarker},
+    rng::GameRng,
+    ship::{ShipConfig, ShipControls},
+    viewport::ViewportBounds,
+};
+
+pub struct HyperspacePlugin;
+
+/// A ship about to jump, with everything that changes as it vanishes
+type JumpingShip<'a> = (
+    Entity,
+    &'a ShipControls,
+    &'a ShipConfig,
+    &'a mut HyperspaceCooldown,
+    &'a mut Collider,
+    &'a mut Visibility,
+    &'a mut Acceleration,
+    &'a mut AngularAcceleration,
+);
+
+/// A ship in hyperspace, with everything that changes as it reappears
+type ReenteringShip<'a> = (
+    Entity,
+    &'a mut InHyperspace,
+    &'a ShipConfig,
+    &'a mut Transform,
+    &'a mut PreviousTransform,
+    &'a mut Velocity,
+    &'a mut Collider,
+    &'a mut Visibility,
+);
+
+impl Plugin for HyperspacePlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.add_system(Self::system_cooldown.in_set(OnUpdate(AppState::InGame)));
+        // the jump is inserted on the ship, so this has to happen before it can be despawned
+        app.add_system(
+            Self::system_jump
+                .before(system_handle_death)
+                .in_set(OnUpdate(AppState::InGame)),
+        );
+        app.add_system(Self::system_reenter.in_set(OnUpdate(AppState::InGame)));
+    }
+}
+
+impl HyperspacePlugin {
+    fn system_cooldown(mut q: Query<&mut HyperspaceCooldown>, time: Res<Time>) {
+        q.iter_mut().for_each(|mut t| {
+            t.0.tick(time.delta());
+        });
+    }
+
+    fn system_jump(
+        mut commands: Commands,
+        mut q: Query<JumpingShip, (With<PlayerMarker>, Without<InHyperspace>)>,
+        bounds: Res<ViewportBounds>,
+        mut rng: ResMut<GameRng>,
+    ) {
+        for (
+            entity,
+            controls,
+            config,
+            mut cooldown,
+            mut collider,
+            mut visibility,
+            mut acc,
+            mut racc,
+        ) in q.iter_mut()
+        {
+            if !controls.hyperspace || !cooldown.0.finished() {
+                continue;
+            }
+            cooldown.0.reset();
+
+            let x_dist = Uniform::new(bounds.0.min.x, bounds.0.max.x);
+            let y_dist = Uniform::new(bounds.0.min.y, bounds.0.max.y);
+            let destination = Vec2::new(rng.rng.sample(x_dist), rng.rng.sample(y_dist));
+
+            // vanish, and stop steering while away
+            collider.layers = CollisionLayers::default();
+            *visibility = Visibility::Hidden;
+            acc.0 = Vec2::ZERO;
+            racc.0 = 0.;
+            commands.entity(entity).insert(InHyperspace {
+                timer: Timer::from_seconds(config.hyperspace_duration, TimerMode::Once),
+                destination,
+            });
+        }
+    }
+
+    fn system_reenter(
+        mut commands: Commands,
+        mut q: Query<ReenteringShip, With<PlayerMarker>>,
+        time: Res<Time>,
+        mut rng: ResMut<GameRng>,
+        mut evw_died: EventWriter<PlayerDied>,
+    ) {
+        for (
+            entity,
+            mut hyperspace,
+            config,
+            mut xform,
+            mut prev,
+            mut vel,
+            mut collider,
+            mut visibility,
+        ) in q.iter_mut()
+        {
+            if !hyperspace.timer.tick(time.delta()).finished() {
+                continue;
+            }
+            // arrive at rest, without streaking across the screen
+            xform.translation = Vec3::from((hyperspace.destination, xform.translation.z));
+            prev.0 = None;
+            vel.0 = Vec2::ZERO;
+            collider.layers = config.collision_layers;
+            *visibility = Visibility::Inherited;
+            commands.entity(entity).remove::<InHyperspace>();
+
+            if rng.rng.gen::<f32>() < config.hyperspace_failure_chance {
+                evw_died.send(PlayerDied(entity));
+            }
+        }
+    }
+}
+
+/// Time until the ship can jump again
+#[derive(Debug, Component)]
+pub struct HyperspaceCooldown(pub Timer);
+
+impl HyperspaceCooldown {
+    pub fn new(seconds: f32) -> Self {
+        // ready to jump right away
+        let mut timer = Timer::from_seconds(seconds, TimerMode::Once);
+        timer.tick(timer.duration());
+        Self(timer)
+    }
+}
+
+/// A ship that is currently jumping through hyperspace
+#[derive(Debug, Component)]
+pub struct InHyperspace {
+    timer: Timer,
+    destination: Vec2,
+}
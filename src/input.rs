@@ -4,22 +4,22 @@ use std::cmp::Ordering;
 
 use bevy::{
     input::{keyboard::KeyboardInput, ButtonState},
-    math::Vec3Swizzles,
     prelude::{
         Camera, Component, EventReader, EventWriter, Events, GlobalTransform, Input,
-        IntoSystemConfig, KeyCode, MouseButton, OnUpdate, Plugin, Query, Res, Transform, With,
+        IntoSystemConfig, KeyCode, MouseButton, Plugin, Query, Res, Transform, With,
     },
     window::Window,
 };
 
-use crate::{app::AppState, collision::Collider, viewport::PrimaryCameraMarker};
+use crate::{app::game_in_progress, collision::Collider, viewport::PrimaryCameraMarker};
 
 pub struct InputPlugin;
 
 impl Plugin for InputPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
         app.add_event::<InputEvent>();
-        app.add_system(system_keyboard_input.in_set(OnUpdate(AppState::InGame)));
+        // a single system for both states, so that no key press is read twice
+        app.add_system(system_keyboard_input.run_if(game_in_progress));
         app.add_system(system_click_input);
     }
 }
@@ -30,6 +30,10 @@ pub enum InputAction {
     Thrust(f32),
     Turn(f32),
     Shoot,
+    Shield,
+    Boost,
+    Hyperspace,
+    Pause,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -55,6 +59,10 @@ pub fn system_keyboard_input(
                 KeyCode::A | KeyCode::Left => Some(InputAction::Turn(1.)),
                 KeyCode::D | KeyCode::Right => Some(InputAction::Turn(-1.)),
                 KeyCode::Space => Some(InputAction::Shoot),
+                KeyCode::E => Some(InputAction::Shield),
+                KeyCode::Q => Some(InputAction::Boost),
+                KeyCode::LShift | KeyCode::RShift => Some(InputAction::Hyperspace),
+                KeyCode::Escape | KeyCode::P => Some(InputAction::Pause),
                 _ => None,
             });
         if let Some(action) = action_opt {
@@ -96,8 +104,7 @@ pub fn system_click_input(
         let mut clicked: Vec<_> = listeners
             .iter_mut()
             .flat_map(|(listener, collider, xform)| {
-                let dist = xform.translation.xy().distance(cursor_pos);
-                if dist <= collider.radius {
+                if collider.contains_point(xform, cursor_pos) {
                     Some((listener, xform))
                     // listener.0.send(input_mouse.clone());
                 } else {
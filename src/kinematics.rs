@@ -1,61 +1,134 @@
+//! Simple kinematics, integrated on a fixed simulation tick.
+//!
+//! Each tick applies acceleration, then damping, then velocity.  Rendered transforms are
+//! interpolated between the last two ticks, so that motion stays smooth at any display rate.
+
 use bevy::{
-    prelude::{Bundle, Component, Plugin, Query, Res, Transform, Vec2, Vec3},
-    time::Time,
+    hierarchy::Parent,
+    prelude::{
+        in_state, Bundle, Component, CoreSchedule, CoreSet, GlobalTransform, IntoSystemAppConfigs,
+        IntoSystemConfig, IntoSystemConfigs, Plugin, Query, Res, SystemSet, Transform, Vec2, Vec3,
+        Without,
+    },
+    time::fixed_timestep::FixedTime,
+    transform::TransformSystem,
 };
 
+use crate::app::AppState;
+
+/// Length of a simulation tick in seconds
+pub const FIXED_TICK_SECONDS: f32 = 1. / 60.;
+
 pub struct KinematicsPlugin;
 
 impl Plugin for KinematicsPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
-        app.add_system(Self::system_velocity);
-        app.add_system(Self::system_acceleration);
-        app.add_system(Self::system_angular_velocity);
-        app.add_system(Self::system_angular_acceleration);
-        app.add_system(Self::system_linear_damping);
-        app.add_system(Self::system_angular_damping);
+        app.insert_resource(FixedTime::new_from_secs(FIXED_TICK_SECONDS));
+        app.add_systems(
+            (
+                Self::system_store_previous,
+                Self::system_acceleration,
+                Self::system_angular_acceleration,
+                Self::system_linear_damping,
+                Self::system_angular_damping,
+                Self::system_velocity,
+                Self::system_angular_velocity,
+            )
+                .chain()
+                .in_set(KinematicsSet)
+                .distributive_run_if(in_state(AppState::InGame))
+                .in_schedule(CoreSchedule::FixedUpdate),
+        );
+        // while paused, rendered transforms stay where the interpolation left them
+        app.add_system(
+            Self::system_interpolate
+                .run_if(in_state(AppState::InGame))
+                .in_base_set(CoreSet::PostUpdate)
+                .after(TransformSystem::TransformPropagate),
+        );
     }
 }
 
+/// Systems that integrate kinematics, in the fixed update schedule.  Systems that need this
+/// tick's positions should run after this set.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemSet)]
+pub struct KinematicsSet;
+
 impl KinematicsPlugin {
-    fn system_velocity(mut q: Query<(&mut Transform, &Velocity)>, time: Res<Time>) {
-        let dt = time.delta_seconds();
+    fn system_store_previous(mut q: Query<(&Transform, &mut PreviousTransform)>) {
+        q.iter_mut()
+            .for_each(|(xform, mut prev)| prev.0 = Some(*xform));
+    }
+
+    fn system_velocity(mut q: Query<(&mut Transform, &Velocity)>, fixed_time: Res<FixedTime>) {
+        let dt = fixed_time.period.as_secs_f32();
         q.iter_mut()
             .for_each(|(mut xform, vel)| xform.translation += Vec3::from((vel.0 * dt, 0.)));
     }
 
-    fn system_angular_velocity(mut q: Query<(&mut Transform, &AngularVelocity)>, time: Res<Time>) {
-        let dt = time.delta_seconds();
+    fn system_angular_velocity(
+        mut q: Query<(&mut Transform, &AngularVelocity)>,
+        fixed_time: Res<FixedTime>,
+    ) {
+        let dt = fixed_time.period.as_secs_f32();
         q.iter_mut()
             .for_each(|(mut xform, rvel)| xform.rotate_z(rvel.0 * dt));
     }
 
-    fn system_acceleration(mut q: Query<(&mut Velocity, &Acceleration)>, time: Res<Time>) {
-        let dt = time.delta_seconds();
+    fn system_acceleration(
+        mut q: Query<(&mut Velocity, &Acceleration)>,
+        fixed_time: Res<FixedTime>,
+    ) {
+        let dt = fixed_time.period.as_secs_f32();
         q.iter_mut().for_each(|(mut vel, acc)| vel.0 += acc.0 * dt);
     }
 
     fn system_angular_acceleration(
         mut q: Query<(&mut AngularVelocity, &AngularAcceleration)>,
-        time: Res<Time>,
+        fixed_time: Res<FixedTime>,
     ) {
-        let dt = time.delta_seconds();
+        let dt = fixed_time.period.as_secs_f32();
         q.iter_mut()
             .for_each(|(mut rvel, racc)| rvel.0 += racc.0 * dt);
     }
 
-    fn system_linear_damping(mut q: Query<(&mut Velocity, &LinearDamping)>, time: Res<Time>) {
-        let dt = time.delta_seconds();
+    /// Exponential damping, which stays stable for any tick length
+    fn system_linear_damping(
+        mut q: Query<(&mut Velocity, &LinearDamping)>,
+        fixed_time: Res<FixedTime>,
+    ) {
+        let dt = fixed_time.period.as_secs_f32();
         q.iter_mut()
-            .for_each(|(mut vel, ldamp)| vel.0 *= 1. - ldamp.0 * dt);
+            .for_each(|(mut vel, ldamp)| vel.0 *= (-ldamp.0 * dt).exp());
     }
 
     fn system_angular_damping(
         mut q: Query<(&mut AngularVelocity, &AngularDamping)>,
-        time: Res<Time>,
+        fixed_time: Res<FixedTime>,
     ) {
-        let dt = time.delta_seconds();
+        let dt = fixed_time.period.as_secs_f32();
         q.iter_mut()
-            .for_each(|(mut rvel, rdamp)| rvel.0 *= 1. - rdamp.0 * dt);
+            .for_each(|(mut rvel, rdamp)| rvel.0 *= (-rdamp.0 * dt).exp());
+    }
+
+    /// Render top level entities between their previous and current simulated transforms,
+    /// according to how far we are into the next tick.
+    fn system_interpolate(
+        mut q: Query<(&Transform, &PreviousTransform, &mut GlobalTransform), Without<Parent>>,
+        fixed_time: Res<FixedTime>,
+    ) {
+        let alpha =
+            (fixed_time.accumulated().as_secs_f32() / fixed_time.period.as_secs_f32()).min(1.);
+        q.iter_mut().for_each(|(xform, prev, mut global_xform)| {
+            if let Some(prev) = prev.0 {
+                let interpolated = Transform {
+                    translation: prev.translation.lerp(xform.translation, alpha),
+                    rotation: prev.rotation.slerp(xform.rotation, alpha),
+                    scale: prev.scale.lerp(xform.scale, alpha),
+                };
+                *global_xform = GlobalTransform::from(interpolated);
+            }
+        });
     }
 }
 
@@ -67,6 +140,7 @@ pub struct KinematicsBundle {
     pub angular_acceleration: AngularAcceleration,
     pub linear_damping: LinearDamping,
     pub angular_damping: AngularDamping,
+    pub previous_transform: PreviousTransform,
 }
 
 #[derive(Debug, Clone, Copy, Default, Component)]
@@ -86,3 +160,8 @@ pub struct LinearDamping(pub f32);
 
 #[derive(Debug, Clone, Copy, Default, Component)]
 pub struct AngularDamping(pub f32);
+
+/// Transform at the start of the latest simulation tick, used for interpolating rendered
+/// transforms.  Teleporting entities should clear it, so that they don't streak across the screen.
+#[derive(Debug, Clone, Copy, Default, Component)]
+pub struct PreviousTransform(pub Option<Transform>);
@@ -1,21 +1,37 @@
 use wasm_bindgen::prelude::wasm_bindgen;
-use web_sys::AudioContext;
 
 mod app;
 mod collision;
 mod config;
+mod enemy;
+mod game_over;
+mod headless;
+mod hud;
+mod hyperspace;
 mod input;
 mod kinematics;
 mod loading;
+mod main_menu;
 mod meteor;
+mod particle;
+mod pause;
 mod player;
+mod powerup;
 mod projectile;
+mod rng;
+mod score;
+mod shape;
 mod ship;
 mod sounds;
 mod splash;
 mod viewport;
+mod wave;
+mod weapon;
+
+pub use app::{run, LaunchOptions};
+pub use headless::{build_app as build_headless_app, run as run_headless};
 
 #[wasm_bindgen]
 pub fn run_app() {
-    app::run();
+    app::run(LaunchOptions::default());
 }
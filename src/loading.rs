@@ -18,9 +18,13 @@ use bevy::{
 use bevy_kira_audio::AudioSource;
 use serde::Deserialize;
 
-use crate::{app::AppState, config::Config, splash::SplashCleanup};
+use crate::{
+    app::{AppState, LaunchOptions},
+    config::Config,
+    splash::SplashCleanup,
+};
 
-const CONFIG_ASSET_PATH: &str = "config.toml";
+pub const CONFIG_ASSET_PATH: &str = "config.toml";
 
 #[derive(Debug)]
 pub struct LoadingPlugin;
@@ -55,9 +59,6 @@ pub struct AssetsConfig {
 #[derive(Debug, Clone, Resource, Default)]
 struct AssetsLoading(Vec<HandleUntyped>);
 
-#[derive(Debug, Clone, Component)]
-struct TitleTextMarker;
-
 #[derive(Debug, Clone, Component)]
 struct StateTextMarker;
 
@@ -65,7 +66,11 @@ struct StateTextMarker;
 struct StatusTextMarker;
 
 /// Initiate asset preloading
-fn system_preload_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn system_preload_setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    options: Res<LaunchOptions>,
+) {
     let loading_font = asset_server.load("fira_sans/FiraSans-Regular.ttf");
     commands.insert_resource(LoadingFont(loading_font));
 
@@ -89,7 +94,7 @@ fn system_preload_setup(mut commands: Commands, asset_server: Res<AssetServer>)
     ));
 
     // start loading config
-    let config_handle: Handle<Config> = asset_server.load(CONFIG_ASSET_PATH);
+    let config_handle: Handle<Config> = asset_server.load(options.config_path.as_str());
 
     // insert loading state resource
     commands.insert_resource(LoadingConfig(config_handle));
@@ -100,11 +105,12 @@ fn system_preload_watch_config(
     asset_server: Res<AssetServer>,
     loading_config: Res<LoadingConfig>,
     configs: Option<Res<Assets<Config>>>,
+    options: Res<LaunchOptions>,
     mut next_state: ResMut<NextState<AppState>>,
 ) {
     match asset_server.get_load_state(loading_config.0.id()) {
         LoadState::Failed => {
-            panic!("Loading asset failed: '{}'", CONFIG_ASSET_PATH);
+            panic!("Loading asset failed: '{}'", options.config_path);
         }
         LoadState::Loaded => {
             // unpack config
@@ -223,7 +229,7 @@ fn system_loading_update(
     status_text.single_mut().sections[0].value = status;
 
     if loaded == loading.0.len() {
-        // TODO: go to main menu when it's implemented
+        // the splash screen comes first, since the web build needs a click before playing audio
         info!("All assets loaded");
         next_state.set(AppState::Splash)
     }
@@ -0,0 +1,71 @@
+//! Native desktop entry point.
+//!
+//! The wasm build enters through `run_app` in the library instead.
+
+use bevy::log::Level;
+use clap::Parser;
+use stroids::LaunchOptions;
+
+/// An asteroid clone made with beautiful kenney assets.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Args {
+    /// Path to the config file, relative to the assets folder
+    #[arg(long, default_value = "config.toml")]
+    config: String,
+    /// Window resolution as WIDTHxHEIGHT, overriding the configured one
+    #[arg(long, value_parser = parse_resolution)]
+    resolution: Option<[f32; 2]>,
+    /// Seed for the random number generator
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Wave to start the game on
+    #[arg(long, default_value_t = 1)]
+    wave: u32,
+    /// Minimum level of log messages to show (trace, debug, info, warn, error)
+    #[arg(long, default_value_t = Level::INFO)]
+    log_level: Level,
+    /// Simulate the game without a window, renderer or audio
+    #[arg(long)]
+    headless: bool,
+    /// Maximum number of fixed ticks to simulate in headless mode, which stops early on game over
+    #[arg(long, default_value_t = 3600, requires = "headless")]
+    ticks: u32,
+}
+
+impl From<Args> for LaunchOptions {
+    fn from(args: Args) -> Self {
+        Self {
+            config_path: args.config,
+            resolution: args.resolution,
+            seed: args.seed,
+            start_wave: args.wave,
+            log_level: args.log_level,
+        }
+    }
+}
+
+fn parse_resolution(s: &str) -> Result<[f32; 2], String> {
+    let (width, height) = s
+        .split_once('x')
+        .ok_or_else(|| format!("expected WIDTHxHEIGHT, got '{}'", s))?;
+    let width: f32 = width
+        .trim()
+        .parse()
+        .map_err(|e| format!("invalid width '{}': {}", width, e))?;
+    let height: f32 = height
+        .trim()
+        .parse()
+        .map_err(|e| format!("invalid height '{}': {}", height, e))?;
+    Ok([width, height])
+}
+
+fn main() {
+    let args = Args::parse();
+    if args.headless {
+        let ticks = args.ticks;
+        stroids::run_headless(args.into(), ticks);
+    } else {
+        stroids::run(args.into());
+    }
+}
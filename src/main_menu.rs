@@ -0,0 +1,208 @@
+//! The main menu, where the player picks a ship and starts the game.
+
+use bevy::{
+    prelude::{
+        default, AssetServer, Color, Commands, Component, Entity, Handle, Image,
+        IntoSystemAppConfig, IntoSystemConfig, MouseButton, NextState, OnEnter, OnExit, OnUpdate,
+        Plugin, Query, Res, ResMut, Resource, Transform, Vec2, Vec3, With,
+    },
+    sprite::{Sprite, SpriteBundle},
+    text::{Text, Text2dBundle, TextAlignment, TextStyle},
+};
+
+use crate::{
+    app::AppState, collision::Collider, input::ClickListener, loading::AssetMap,
+    player::PlayerConfig, shape::ColliderShape,
+};
+
+/// Images with this prefix can be picked as the player's ship
+const SHIP_SPRITE_PREFIX: &str = "player_ship_";
+const SHIP_SPACING: f32 = 320.;
+const SHIP_SCALE: f32 = 2.;
+const CONTROLS_TEXT: &str = "Left/Right/A/D: Turn\n\
+                             Forward/Back/W/S: Thrust\n\
+                             Space: Shoot\n\
+                             E: Shield, Q: Boost\n\
+                             Shift: Hyperspace\n\
+                             Escape/P: Pause";
+
+#[derive(Debug)]
+pub struct MainMenuPlugin;
+
+impl Plugin for MainMenuPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.add_system(Self::system_setup.in_schedule(OnEnter(AppState::MainMenu)));
+        app.add_system(Self::system_handle_ship_click.in_set(OnUpdate(AppState::MainMenu)));
+        app.add_system(Self::system_update_ship_highlight.in_set(OnUpdate(AppState::MainMenu)));
+        app.add_system(Self::system_handle_start_click.in_set(OnUpdate(AppState::MainMenu)));
+        app.add_system(Self::system_cleanup.in_schedule(OnExit(AppState::MainMenu)));
+    }
+}
+
+impl MainMenuPlugin {
+    fn system_setup(
+        mut commands: Commands,
+        asset_server: Res<AssetServer>,
+        loaded_images: Res<AssetMap<Image>>,
+        player_config: Res<PlayerConfig>,
+        selection: Option<Res<ShipSelection>>,
+    ) {
+        // keep the ship from the last visit to the menu, or start with the configured one
+        if selection.is_none() {
+            commands.insert_resource(ShipSelection(player_config.ship.sprite_id.clone()));
+        }
+
+        let font = asset_server.load("fira_sans/FiraSans-Regular.ttf");
+        let heading_text_style = TextStyle {
+            font_size: 96.0,
+            color: Color::WHITE,
+            font: font.clone(),
+        };
+        let body_text_style = TextStyle {
+            font_size: 64.0,
+            color: Color::WHITE,
+            font,
+        };
+
+        // spawn heading
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section("Choose Your Ship", heading_text_style),
+                transform: Transform::from_translation(Vec3::new(0., 512., 0.)),
+                ..default()
+            },
+            MainMenuCleanup,
+        ));
+
+        // spawn a row of ships to pick from
+        let mut ship_ids: Vec<&String> = loaded_images
+            .0
+            .keys()
+            .filter(|id| id.starts_with(SHIP_SPRITE_PREFIX))
+            .collect();
+        ship_ids.sort();
+        let row_start = -SHIP_SPACING * (ship_ids.len() as f32 - 1.) / 2.;
+        for (i, ship_id) in ship_ids.into_iter().enumerate() {
+            let texture: Handle<Image> = loaded_images.0[ship_id].clone();
+            commands.spawn((
+                SpriteBundle {
+                    texture,
+                    transform: Transform {
+                        translation: Vec3::new(row_start + SHIP_SPACING * i as f32, 224., 0.),
+                        scale: Vec3::ONE * SHIP_SCALE,
+                        ..default()
+                    },
+                    ..default()
+                },
+                ClickListener::default(),
+                Collider {
+                    shape: ColliderShape::Circle {
+                        radius: SHIP_SPACING / 2.,
+                    },
+                    ..default()
+                },
+                ShipChoice(ship_id.clone()),
+                MainMenuCleanup,
+            ));
+        }
+
+        // spawn controls text
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(CONTROLS_TEXT, body_text_style.clone())
+                    .with_alignment(TextAlignment::Center),
+                transform: Transform::from_translation(Vec3::new(0., -192., 0.)),
+                ..default()
+            },
+            MainMenuCleanup,
+        ));
+
+        // spawn start button
+        let half_size = Vec2::new(256., 64.);
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section("Start", body_text_style),
+                transform: Transform::from_translation(Vec3::new(0., -576., 0.)),
+                ..default()
+            },
+            ClickListener::default(),
+            Collider {
+                shape: ColliderShape::Polygon {
+                    points: vec![
+                        Vec2::new(-half_size.x, -half_size.y),
+                        Vec2::new(half_size.x, -half_size.y),
+                        Vec2::new(half_size.x, half_size.y),
+                        Vec2::new(-half_size.x, half_size.y),
+                    ],
+                },
+                ..default()
+            },
+            StartButton,
+            MainMenuCleanup,
+        ));
+    }
+
+    fn system_handle_ship_click(
+        mut q: Query<(&mut ClickListener, &ShipChoice)>,
+        mut selection: ResMut<ShipSelection>,
+        mut player_config: ResMut<PlayerConfig>,
+    ) {
+        for (mut listener, choice) in q.iter_mut() {
+            let clicked = listener
+                .0
+                .drain()
+                .any(|ev| ev.just_pressed(MouseButton::Left));
+            if clicked {
+                selection.0 = choice.0.clone();
+                player_config.ship.sprite_id = choice.0.clone();
+            }
+        }
+    }
+
+    /// Fade out the ships that aren't selected
+    fn system_update_ship_highlight(
+        mut q: Query<(&ShipChoice, &mut Sprite)>,
+        selection: Res<ShipSelection>,
+    ) {
+        for (choice, mut sprite) in q.iter_mut() {
+            sprite.color = if choice.0 == selection.0 {
+                Color::WHITE
+            } else {
+                Color::rgba(1., 1., 1., 0.3)
+            };
+        }
+    }
+
+    fn system_handle_start_click(
+        mut q: Query<&mut ClickListener, With<StartButton>>,
+        mut next_state: ResMut<NextState<AppState>>,
+    ) {
+        let events: Vec<_> = q
+            .iter_mut()
+            .flat_map(|mut listener| listener.0.drain().collect::<Vec<_>>())
+            .collect();
+        if !events.is_empty() {
+            next_state.set(AppState::InGame);
+        }
+    }
+
+    fn system_cleanup(mut commands: Commands, q: Query<Entity, With<MainMenuCleanup>>) {
+        q.iter().for_each(|e| commands.entity(e).despawn());
+    }
+}
+
+/// The player's ship sprite, as picked in the main menu.  It takes precedence over the configured
+/// ship for the rest of the session.
+#[derive(Debug, Clone, Resource)]
+pub struct ShipSelection(pub String);
+
+/// A ship that can be picked in the main menu, by sprite id
+#[derive(Debug, Component)]
+pub struct ShipChoice(String);
+
+// entities with this component will be cleaned up when leaving the main menu.
+#[derive(Debug, Component)]
+pub struct MainMenuCleanup;
+
+#[derive(Debug, Component)]
+pub struct StartButton;
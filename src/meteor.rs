@@ -1,44 +1,99 @@
-use std::f32::consts::PI;
+use std::{collections::BTreeMap, f32::consts::PI};
 
 use bevy::{
+    ecs::system::SystemParam,
+    math::Vec3Swizzles,
     prelude::{
-        Bundle, Commands, Component, Entity, Image, IntoSystemAppConfig, IntoSystemConfig, OnEnter,
-        OnUpdate, Plugin, Query, Res, Resource, Transform, Vec2, Vec3, With,
+        in_state, warn, Bundle, Color, Commands, Component, CoreSchedule, Entity, EventReader,
+        EventWriter, Image, IntoSystemAppConfig, IntoSystemConfig, OnUpdate, Plugin, Query, Res,
+        ResMut, Resource, Transform, Vec2, Vec3, With, Without,
     },
-    sprite::SpriteBundle,
-    utils::{default, HashMap},
+    sprite::{Sprite, SpriteBundle},
+    time::{Time, Timer, TimerMode},
+    utils::{default, HashMap, HashSet},
+};
+use rand::{
+    distributions::{Uniform, WeightedIndex},
+    Rng,
 };
-use bevy_kira_audio::{Audio, AudioControl, AudioSource};
-use rand::{distributions::Uniform, thread_rng, Rng};
 use serde::Deserialize;
 
 use crate::{
-    app::AppState,
-    collision::Collider,
+    app::{AppState, InGameCleanup},
+    collision::{Collider, CollisionLayers, CollisionSet, CollisionStarted, Contacts},
     kinematics::{AngularVelocity, KinematicsBundle, Velocity},
     loading::AssetMap,
-    player::PlayerMarker,
+    particle::{Explosions, ParticleBurst},
+    player::{PlayerDied, PlayerMarker},
     projectile::ProjectileComponent,
+    rng::GameRng,
+    score::PointsScored,
+    shape::ColliderShape,
+    ship::Shield,
     viewport::{ViewportBounded, ViewportBounds},
+    wave::{Wave, WaveConfig, WaveStarted},
 };
 
+/// How many random positions to try for each meteor of a wave, to keep it away from the player
+const SPAWN_ATTEMPTS: usize = 32;
+/// Tint for meteors that were just hit
+const HIT_FLASH_TINT: Color = Color::rgb(1., 0.4, 0.3);
+
 #[derive(Debug)]
 pub struct MeteorPlugin;
 
 impl Plugin for MeteorPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
-        app.add_system(MeteorBundle::system_spawn.in_schedule(OnEnter(AppState::InGame)));
+        app.add_event::<MeteorDestroyed>();
+        app.add_system(
+            MeteorBundle::system_spawn_wave
+                .after(Wave::system_update)
+                .in_set(OnUpdate(AppState::InGame)),
+        );
         app.add_system(
             MeteorBundle::system_handle_player_collision.in_set(OnUpdate(AppState::InGame)),
         );
         app.add_system(
             MeteorBundle::system_handle_projectile_collision.in_set(OnUpdate(AppState::InGame)),
         );
+        app.add_system(MeteorBundle::system_hit_flash.in_set(OnUpdate(AppState::InGame)));
+        app.add_system(
+            MeteorBundle::system_bounce
+                .after(CollisionSet)
+                .run_if(in_state(AppState::InGame))
+                .in_schedule(CoreSchedule::FixedUpdate),
+        );
     }
 }
 
+/// Sent when the player destroys a meteor
+#[derive(Debug, Clone, Copy)]
+pub struct MeteorDestroyed {
+    pub size: MeteorSize,
+    pub position: Vec3,
+}
+
+/// What's needed to spawn meteors, whether for a new wave or by splitting one
+#[derive(SystemParam)]
+struct MeteorSpawner<'w> {
+    config: Res<'w, MeteorsConfig>,
+    images: Res<'w, AssetMap<Image>>,
+    rng: ResMut<'w, GameRng>,
+}
+
+/// Effects and events of meteors getting hit and destroyed
+#[derive(SystemParam)]
+struct MeteorHits<'w> {
+    explosions: Explosions<'w>,
+    points: EventWriter<'w, PointsScored>,
+    destroyed: EventWriter<'w, MeteorDestroyed>,
+}
+
+/// Meteors, as opposed to the player, which can be queried for some of the same components
+type MeteorFilter = (With<MeteorBehavior>, Without<PlayerMarker>);
+
 #[derive(Debug, Default, Clone, Copy, Hash, PartialEq, Eq, Deserialize)]
-enum MeteorSize {
+pub enum MeteorSize {
     Tiny,
     Small,
     Medium,
@@ -47,23 +102,32 @@ enum MeteorSize {
 }
 
 impl MeteorSize {
-    fn smaller(&self) -> Option<MeteorSize> {
+    /// Mass at a scale of 1, roughly following the area of the sprites for each size
+    fn base_mass(&self) -> f32 {
         match self {
-            Self::Large => Some(Self::Medium),
-            Self::Medium => Some(Self::Small),
-            Self::Small => Some(Self::Tiny),
-            Self::Tiny => None,
+            Self::Large => 1.,
+            Self::Medium => 0.25,
+            Self::Small => 0.1,
+            Self::Tiny => 0.04,
         }
     }
-
-    fn can_split(&self) -> bool {
-        matches!(self, Self::Medium | Self::Large)
-    }
 }
 
 #[derive(Debug, Default, Clone, Deserialize, Resource)]
 pub struct MeteorsConfig {
-    variants: HashMap<String, MeteorConfig>,
+    /// variants are kept in a stable order, so that seeded games roll the same variants
+    variants: BTreeMap<String, MeteorConfig>,
+    collision_layers: CollisionLayers,
+    collision_mask: CollisionLayers,
+    /// fraction of the speed that meteors keep when bouncing off each other, 1 being elastic
+    restitution: f32,
+    /// how much meteors grip each other when bouncing, which transfers spin
+    friction: f32,
+    /// seconds that meteors flash for when hit
+    hit_flash: f32,
+    /// particle effect for bits chipped off meteors by hits that don't destroy them, by variant
+    #[serde(default)]
+    debris: HashMap<String, String>,
 }
 
 /// Configuration for a meteor.
@@ -72,12 +136,36 @@ pub struct MeteorsConfig {
 struct MeteorConfig(HashMap<MeteorSize, SizedMeteorConfig>);
 
 /// Configuration for a meteor of a particular size.
+/// Collision shapes are in sprite pixels, before scaling.
 #[derive(Debug, Clone, Deserialize)]
 struct SizedMeteorConfig {
     sprites: Vec<String>,
     speed: f32,
     scale: f32,
-    collision_radius: f32,
+    /// points for destroying a meteor of this size and variant
+    points: u32,
+    /// damage the meteor takes before it splits or breaks
+    hit_points: u32,
+    /// shape for sprites that don't have their own
+    collision_shape: ColliderShape,
+    /// shapes fitted to individual sprites, by sprite id
+    #[serde(default)]
+    sprite_collision_shapes: HashMap<String, ColliderShape>,
+    /// what the meteor breaks into when shot, if anything
+    #[serde(default)]
+    split: Option<SplitConfig>,
+}
+
+/// How a meteor breaks up when shot.  Fragments are of the same variant.
+#[derive(Debug, Clone, Deserialize)]
+struct SplitConfig {
+    size: MeteorSize,
+    count: u32,
+    /// angle in radians that fragments fan out over, around the direction of the hit
+    spread: f32,
+    /// fragment speeds vary randomly by up to this fraction
+    #[serde(default)]
+    speed_variance: f32,
 }
 
 impl Default for SizedMeteorConfig {
@@ -86,20 +174,37 @@ impl Default for SizedMeteorConfig {
             sprites: Default::default(),
             speed: Default::default(),
             scale: 1.,
-            collision_radius: 100.,
+            points: Default::default(),
+            hit_points: 1,
+            collision_shape: ColliderShape::Circle { radius: 100. },
+            sprite_collision_shapes: Default::default(),
+            split: None,
         }
     }
 }
 
 #[derive(Debug, Default, Component)]
-struct MeteorBehavior {
+pub struct MeteorBehavior {
     size: MeteorSize,
     variant: String,
+    mass: f32,
+    /// speed multiplier of the wave that the meteor belongs to, passed on to fragments
+    speed_multiplier: f32,
 }
 
+/// Damage a meteor can still take
+#[derive(Debug, Default, Component)]
+pub struct MeteorHealth(pub u32);
+
+/// Tints a meteor for a moment after it is hit
+#[derive(Debug, Component)]
+struct HitFlash(Timer);
+
 #[derive(Bundle, Default)]
 struct MeteorBundle {
     behavior: MeteorBehavior,
+    health: MeteorHealth,
+    cleanup: InGameCleanup,
     viewport_bounded: ViewportBounded,
     collider: Collider,
     #[bundle]
@@ -116,12 +221,13 @@ impl MeteorBundle {
         config: &MeteorsConfig,
         images: &AssetMap<Image>,
         variant: Option<&String>,
+        speed_multiplier: f32,
     ) -> Self {
         // roll random meteor variant
         let variant_key = variant.unwrap_or_else(|| {
             let variant_dist = Uniform::new(0, config.variants.len());
             let variant_idx = rng.sample(variant_dist);
-            config.variants.keys().take(variant_idx + 1).last().unwrap()
+            config.variants.keys().nth(variant_idx).unwrap()
         });
         let meteor_config = &config.variants[variant_key].0[&size];
 
@@ -130,11 +236,15 @@ impl MeteorBundle {
         let sprite_idx = rng.sample(sprite_dist);
         let sprite_id = &meteor_config.sprites[sprite_idx];
         let sprite_handle = images.0[sprite_id].clone();
+        let collision_shape = meteor_config
+            .sprite_collision_shapes
+            .get(sprite_id)
+            .unwrap_or(&meteor_config.collision_shape);
 
         // roll random direction
         let angle_dist = Uniform::new(0., PI * 2.);
         let angle = rng.sample(angle_dist);
-        let velocity = Velocity(Vec2::from_angle(angle) * meteor_config.speed);
+        let velocity = Velocity(Vec2::from_angle(angle) * meteor_config.speed * speed_multiplier);
 
         // roll random rotation_velocity
         let angular_velocity_max = PI / 4.;
@@ -146,7 +256,10 @@ impl MeteorBundle {
             behavior: MeteorBehavior {
                 size,
                 variant: String::from(variant_key),
+                mass: size.base_mass() * meteor_config.scale.powi(2),
+                speed_multiplier,
             },
+            health: MeteorHealth(meteor_config.hit_points),
             sprite_bundle: SpriteBundle {
                 texture: sprite_handle,
                 transform: Transform {
@@ -157,7 +270,9 @@ impl MeteorBundle {
                 ..default()
             },
             collider: Collider {
-                radius: meteor_config.collision_radius * meteor_config.scale,
+                shape: collision_shape.scaled(meteor_config.scale),
+                layers: config.collision_layers,
+                mask: config.collision_mask,
             },
             kinematics: KinematicsBundle {
                 velocity,
@@ -168,107 +283,336 @@ impl MeteorBundle {
         }
     }
 
-    fn system_spawn(
+    fn system_spawn_wave(
         mut commands: Commands,
-        meteors_config: Res<MeteorsConfig>,
-        images: Res<AssetMap<Image>>,
+        mut evr_wave_started: EventReader<WaveStarted>,
+        wave_config: Res<WaveConfig>,
         viewport_bounds: Res<ViewportBounds>,
+        q_player: Query<&Transform, With<PlayerMarker>>,
+        mut spawner: MeteorSpawner,
     ) {
-        let mut rng = thread_rng();
-
-        // TODO: level progression
-        let bundles: Vec<MeteorBundle> = (0..4)
-            .map(|_| {
-                // roll position - make sure it's not too close to the center where the player is
-                // TODO: make configurable
-                let mut pos = Vec3::ZERO;
-                while pos.distance(Vec3::ZERO) < 300. {
+        let MeteorSpawner {
+            config: meteors_config,
+            images,
+            rng,
+        } = &mut spawner;
+        let rng = &mut rng.rng;
+        // keep away from the player, or from where it will respawn
+        let player_pos = q_player
+            .get_single()
+            .map_or(Vec2::ZERO, |xform| xform.translation.xy());
+
+        for WaveStarted(wave) in evr_wave_started.iter() {
+            let count = wave_config.meteor_count.at(*wave).round().max(0.) as usize;
+            let speed_multiplier = wave_config.speed_multiplier.at(*wave);
+            // roll variants from the wave's mix, or evenly if there is no mix
+            let variant_keys: Vec<&String> = meteors_config.variants.keys().collect();
+            let variant_dist = WeightedIndex::new(variant_keys.iter().map(|key| {
+                wave_config
+                    .variant_weights
+                    .get(*key)
+                    .map_or(0., |curve| curve.at(*wave).max(0.))
+            }))
+            .ok();
+
+            let bundles: Vec<MeteorBundle> = (0..count)
+                .map(|_| {
+                    // roll position - make sure it's not too close to the player
                     let x_dist = Uniform::new(viewport_bounds.0.min.x, viewport_bounds.0.max.x);
                     let y_dist = Uniform::new(viewport_bounds.0.min.y, viewport_bounds.0.max.y);
-                    let x = rng.sample(x_dist);
-                    let y = rng.sample(y_dist);
-                    pos = Vec3::new(x, y, 0.);
-                }
+                    // the clearance might not fit in the viewport, so settle for the farthest
+                    // position rolled if none is far enough
+                    let distance =
+                        |pos: Vec2| viewport_bounds.wrap_delta(pos - player_pos).length();
+                    let mut pos = player_pos;
+                    for _ in 0..SPAWN_ATTEMPTS {
+                        if distance(pos) >= wave_config.spawn_clearance {
+                            break;
+                        }
+                        let candidate = Vec2::new(rng.sample(x_dist), rng.sample(y_dist));
+                        if distance(candidate) > distance(pos) {
+                            pos = candidate;
+                        }
+                    }
 
-                // roll meteor
-                Self::new_random(
-                    &mut rng,
-                    MeteorSize::Large,
-                    pos,
-                    &meteors_config,
-                    &images,
-                    None,
-                )
-            })
-            .collect();
-        commands.spawn_batch(bundles);
+                    // roll meteor
+                    let variant = variant_dist
+                        .as_ref()
+                        .map(|dist| variant_keys[rng.sample(dist)]);
+                    Self::new_random(
+                        rng,
+                        MeteorSize::Large,
+                        Vec3::from((pos, 0.)),
+                        meteors_config,
+                        images,
+                        variant,
+                        speed_multiplier,
+                    )
+                })
+                .collect();
+            commands.spawn_batch(bundles);
+        }
     }
 
+    /// Meteors touching the player destroy it, or are bounced away while its shield is up.  This
+    /// looks at every ongoing contact, so that a meteor still overlapping the ship when its
+    /// shield drops hits it, and one that is still inside a shield is pushed out again.
     fn system_handle_player_collision(
+        contacts: Res<Contacts>,
+        mut q_meteors: Query<(&mut Transform, &mut Velocity, &Collider), MeteorFilter>,
+        q_player: Query<(&Transform, &Velocity, &Collider, &Shield), With<PlayerMarker>>,
+        viewport_bounds: Res<ViewportBounds>,
+        mut evw_died: EventWriter<PlayerDied>,
+    ) {
+        // the player can touch several meteors in the same tick, but only dies once
+        let mut destroyed: HashSet<Entity> = HashSet::default();
+        for (a, b) in contacts.iter() {
+            let (player_entity, meteor_entity) = if q_player.contains(a) { (a, b) } else { (b, a) };
+            let Ok((mut meteor_xform, mut meteor_vel, meteor_collider)) =
+                q_meteors.get_mut(meteor_entity)
+            else {
+                continue;
+            };
+            let Ok((player_xform, player_vel, player_collider, shield)) =
+                q_player.get(player_entity)
+            else {
+                continue;
+            };
+            if destroyed.contains(&player_entity) {
+                continue;
+            }
+            // contacts are only updated on fixed ticks, so the meteor may have been pushed out
+            // since
+            let Some(contact) = Collider::contact(
+                (player_xform, player_collider),
+                (&meteor_xform, meteor_collider),
+                Some(&viewport_bounds),
+            ) else {
+                continue;
+            };
+            if !shield.0 {
+                destroyed.insert(player_entity);
+                evw_died.send(PlayerDied(player_entity));
+                continue;
+            }
+
+            // the shield knocks the meteor away, as if the ship were immovable
+            let normal_speed = (meteor_vel.0 - player_vel.0).dot(contact.normal);
+            if normal_speed < 0. {
+                meteor_vel.0 -= 2. * normal_speed * contact.normal;
+            }
+            meteor_xform.translation += Vec3::from((contact.normal * contact.depth, 0.));
+        }
+    }
+
+    fn system_handle_projectile_collision(
         mut commands: Commands,
-        q_meteors: Query<(&Transform, &Collider), With<MeteorBehavior>>,
-        q_player: Query<(Entity, &Transform, &Collider), With<PlayerMarker>>,
-        audio_sources: Res<AssetMap<AudioSource>>,
-        audio: Res<Audio>,
+        mut evr_collisions: EventReader<CollisionStarted>,
+        mut q_meteors: Query<(&Transform, &Velocity, &MeteorBehavior, &mut MeteorHealth)>,
+        q_projectile: Query<(&Transform, &Velocity, &ProjectileComponent)>,
+        mut spawner: MeteorSpawner,
+        mut hits: MeteorHits,
     ) {
-        let sound = audio_sources.0.get("explosion").unwrap();
-        for (player_entity, player_xform, player_collider) in q_player.iter() {
-            for (meteor_xform, meteor_collider) in q_meteors.iter() {
-                if Collider::is_collision(
-                    (player_xform, player_collider),
-                    (meteor_xform, meteor_collider),
-                ) {
-                    // TODO: emit & handle player death event
-                    commands.entity(player_entity).despawn();
-                    audio.play(sound.clone());
+        let MeteorSpawner {
+            config: meteors_config,
+            images,
+            rng,
+        } = &mut spawner;
+        // projectiles and meteors can only be destroyed once, even if they hit several things
+        let mut destroyed: HashSet<Entity> = HashSet::default();
+        for ev in evr_collisions.iter() {
+            let Some((projectile_entity, meteor_entity)) = ev.ordered(|e| q_projectile.contains(e))
+            else {
+                continue;
+            };
+            let Ok((meteor_xform, meteor_vel, meteor_behavior, mut health)) =
+                q_meteors.get_mut(meteor_entity)
+            else {
+                continue;
+            };
+            let Ok((projectile_xform, projectile_vel, projectile)) =
+                q_projectile.get(projectile_entity)
+            else {
+                continue;
+            };
+            if destroyed.contains(&projectile_entity) || destroyed.contains(&meteor_entity) {
+                continue;
+            }
+            destroyed.insert(projectile_entity);
+            commands.entity(projectile_entity).despawn();
+            let variant_config = &meteors_config.variants[&meteor_behavior.variant];
+            let impact_dir = projectile_vel.0.try_normalize().unwrap_or(Vec2::Y);
+            hits.explosions.particles.send(
+                ParticleBurst::new("impact", projectile_xform.translation)
+                    .with_direction(-impact_dir)
+                    .with_velocity(meteor_vel.0),
+            );
+
+            health.0 = health.0.saturating_sub(projectile.damage);
+            if health.0 > 0 {
+                // chip off some debris, back towards the shooter
+                if let Some(effect) = meteors_config.debris.get(&meteor_behavior.variant) {
+                    hits.explosions.particles.send(
+                        ParticleBurst::new(effect, meteor_xform.translation)
+                            .with_direction(-impact_dir)
+                            .with_velocity(meteor_vel.0),
+                    );
                 }
+                commands
+                    .entity(meteor_entity)
+                    .insert(HitFlash(Timer::from_seconds(
+                        meteors_config.hit_flash,
+                        TimerMode::Once,
+                    )));
+                continue;
+            }
+            destroyed.insert(meteor_entity);
+            commands.entity(meteor_entity).despawn();
+            let meteor_config = &variant_config.0[&meteor_behavior.size];
+            hits.points.send(PointsScored(meteor_config.points));
+            hits.destroyed.send(MeteorDestroyed {
+                size: meteor_behavior.size,
+                position: meteor_xform.translation,
+            });
+            hits.explosions
+                .explode(meteor_xform.translation, meteor_vel.0);
+            // split meteor if possible
+            let split = meteor_config.split.as_ref().filter(|split| {
+                let known = variant_config.0.contains_key(&split.size);
+                if !known {
+                    warn!(
+                        "Meteor variant {} has no {:?} size to split into",
+                        meteor_behavior.variant, split.size
+                    );
+                }
+                known
+            });
+            if let Some(split) = split {
+                let count = split.count;
+                // fragments keep the parent's momentum, and fan out in the direction of the hit
+                let children: Vec<MeteorBundle> = (0..count)
+                    .map(|i| {
+                        let mut child = MeteorBundle::new_random(
+                            &mut rng.rng,
+                            split.size,
+                            meteor_xform.translation,
+                            meteors_config,
+                            images,
+                            Some(&meteor_behavior.variant),
+                            meteor_behavior.speed_multiplier,
+                        );
+                        let offset = if count > 1 {
+                            (i as f32 / (count - 1) as f32 - 0.5) * split.spread
+                        } else {
+                            0.
+                        };
+                        let variance = if split.speed_variance > 0. {
+                            rng.rng.sample(Uniform::new_inclusive(
+                                -split.speed_variance,
+                                split.speed_variance,
+                            ))
+                        } else {
+                            0.
+                        };
+                        let speed = child.kinematics.velocity.0.length() * (1. + variance);
+                        child.kinematics.velocity.0 =
+                            meteor_vel.0 + Vec2::from_angle(offset).rotate(impact_dir) * speed;
+                        child
+                    })
+                    .collect();
+                commands.spawn_batch(children);
             }
         }
     }
 
-    fn system_handle_projectile_collision(
+    fn system_hit_flash(
         mut commands: Commands,
-        q_meteors: Query<(Entity, &Transform, &Collider, &MeteorBehavior)>,
-        q_projectile: Query<(Entity, &Transform, &Collider), With<ProjectileComponent>>,
+        mut q: Query<(Entity, &mut HitFlash, &mut Sprite)>,
+        time: Res<Time>,
+    ) {
+        for (entity, mut flash, mut sprite) in q.iter_mut() {
+            if flash.0.tick(time.delta()).finished() {
+                sprite.color = Color::WHITE;
+                commands.entity(entity).remove::<HitFlash>();
+            } else {
+                sprite.color = HIT_FLASH_TINT;
+            }
+        }
+    }
+
+    /// Bounce touching meteors off each other, conserving momentum, and push them apart so that
+    /// they stop overlapping.  This also separates fragments that were split on top of each other.
+    fn system_bounce(
+        contacts: Res<Contacts>,
+        mut q_meteors: Query<(
+            &mut Transform,
+            &mut Velocity,
+            &mut AngularVelocity,
+            &Collider,
+            &MeteorBehavior,
+        )>,
+        viewport_bounds: Res<ViewportBounds>,
         meteors_config: Res<MeteorsConfig>,
-        images: Res<AssetMap<Image>>,
-        audio_sources: Res<AssetMap<AudioSource>>,
-        audio: Res<Audio>,
     ) {
-        // TODO: configure
-        let sound = audio_sources.0.get("explosion").unwrap();
-        for (projectile_entity, projectile_xform, projectile_collider) in q_projectile.iter() {
-            for (meteor_entity, meteor_xform, meteor_collider, meteor_behavior) in q_meteors.iter()
-            {
-                if Collider::is_collision(
-                    (projectile_xform, projectile_collider),
-                    (meteor_xform, meteor_collider),
-                ) {
-                    // destroy projectile and meteor
-                    commands.entity(projectile_entity).despawn();
-                    commands.entity(meteor_entity).despawn();
-                    // split meteor if possible
-                    if meteor_behavior.size.can_split() {
-                        let mut rng = thread_rng();
-                        // TODO: configure split
-                        let children: Vec<MeteorBundle> = (0..2)
-                            .map(|_i| {
-                                MeteorBundle::new_random(
-                                    &mut rng,
-                                    meteor_behavior.size.smaller().unwrap(),
-                                    meteor_xform.translation,
-                                    &meteors_config,
-                                    &images,
-                                    Some(&meteor_behavior.variant),
-                                )
-                            })
-                            .collect();
-                        commands.spawn_batch(children);
-                    }
-                    // play explosion sound
-                    audio.play(sound.clone());
+        for (a, b) in contacts.iter() {
+            let Ok([meteor_a, meteor_b]) = q_meteors.get_many_mut([a, b]) else {
+                continue;
+            };
+            let (mut xform_a, mut vel_a, mut rvel_a, collider_a, behavior_a) = meteor_a;
+            let (mut xform_b, mut vel_b, mut rvel_b, collider_b, behavior_b) = meteor_b;
+            let Some(contact) = Collider::contact(
+                (&xform_a, collider_a),
+                (&xform_b, collider_b),
+                Some(&viewport_bounds),
+            ) else {
+                continue;
+            };
+
+            // treat meteors as discs when working out how hard they are to spin
+            let inv_mass_a = 1. / behavior_a.mass;
+            let inv_mass_b = 1. / behavior_b.mass;
+            let inv_inertia_a = 2. * inv_mass_a / collider_a.shape.bounding_radius().powi(2);
+            let inv_inertia_b = 2. * inv_mass_b / collider_b.shape.bounding_radius().powi(2);
+
+            // contact point relative to each meteor
+            let delta =
+                viewport_bounds.wrap_delta((xform_b.translation - xform_a.translation).xy());
+            let r_a = contact.point;
+            let r_b = contact.point - delta;
+
+            let normal = contact.normal;
+            let point_vel_a = vel_a.0 + rvel_a.0 * r_a.perp();
+            let point_vel_b = vel_b.0 + rvel_b.0 * r_b.perp();
+            let rel_vel = point_vel_b - point_vel_a;
+            let normal_speed = rel_vel.dot(normal);
+            // only bounce meteors that are moving towards each other
+            if normal_speed < 0. {
+                let inv_effective_mass = |dir: Vec2| {
+                    inv_mass_a
+                        + inv_mass_b
+                        + r_a.perp_dot(dir).powi(2) * inv_inertia_a
+                        + r_b.perp_dot(dir).powi(2) * inv_inertia_b
+                };
+                let normal_impulse =
+                    -(1. + meteors_config.restitution) * normal_speed / inv_effective_mass(normal);
+                let mut impulse = normal * normal_impulse;
+                // friction along the surface, limited by how hard they hit
+                if let Some(tangent) = (rel_vel - normal * normal_speed).try_normalize() {
+                    let max_friction = meteors_config.friction * normal_impulse;
+                    let friction_impulse = (-rel_vel.dot(tangent) / inv_effective_mass(tangent))
+                        .clamp(-max_friction, max_friction);
+                    impulse += tangent * friction_impulse;
                 }
+                vel_a.0 -= impulse * inv_mass_a;
+                rvel_a.0 -= r_a.perp_dot(impulse) * inv_inertia_a;
+                vel_b.0 += impulse * inv_mass_b;
+                rvel_b.0 += r_b.perp_dot(impulse) * inv_inertia_b;
             }
+
+            // push them apart, moving the lighter meteor further
+            let separation = normal * contact.depth / (inv_mass_a + inv_mass_b);
+            xform_a.translation -= Vec3::from((separation * inv_mass_a, 0.));
+            xform_b.translation += Vec3::from((separation * inv_mass_b, 0.));
         }
     }
 }
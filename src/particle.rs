@@ -0,0 +1,440 @@
+//! Particle effects, defined in the config under `[particles.*]`.
+//!
+//! Effects are either fired once at a position with a `ParticleBurst` event, or emitted
+//! continuously by a `ParticleEmitter` attached to an entity.  Particles are colored squares, or
+//! images like bits of rock.  They are pooled instead of despawned, so that explosions don't churn
+//! through entities, which is slow on wasm.
+
+use std::f32::consts::PI;
+
+use bevy::{
+    ecs::system::SystemParam,
+    math::Vec3Swizzles,
+    prelude::{
+        warn, Color, Commands, Component, Entity, EventReader, EventWriter, Handle, Image,
+        IntoSystemAppConfig, IntoSystemConfig, OnExit, OnUpdate, Plugin, Quat, Query, Res, ResMut,
+        Resource, Transform, Vec2, Vec3, Visibility, Without,
+    },
+    sprite::{Sprite, SpriteBundle},
+    time::Time,
+    utils::{default, HashMap},
+};
+use rand::{distributions::Uniform, thread_rng, Rng};
+use serde::Deserialize;
+
+use crate::{
+    app::{ending_game, AppState},
+    kinematics::Velocity,
+    loading::AssetMap,
+    sounds::SoundEvent,
+    viewport::ViewportBounded,
+};
+
+/// Most particles alive at once.  Effects are cut short beyond this.
+const MAX_PARTICLES: usize = 2048;
+/// Particles are drawn above meteors and ships
+const PARTICLE_Z: f32 = 1.;
+
+#[derive(Debug)]
+pub struct ParticlePlugin;
+
+impl Plugin for ParticlePlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.init_resource::<ParticlePool>();
+        app.add_event::<ParticleBurst>();
+        app.add_system(Particle::system_spawn.in_set(OnUpdate(AppState::InGame)));
+        app.add_system(
+            Particle::system_update
+                .after(Particle::system_spawn)
+                .in_set(OnUpdate(AppState::InGame)),
+        );
+        app.add_system(
+            Particle::system_release_all
+                .run_if(ending_game)
+                .in_schedule(OnExit(AppState::InGame)),
+        );
+        app.add_system(
+            Particle::system_release_all
+                .run_if(ending_game)
+                .in_schedule(OnExit(AppState::Paused)),
+        );
+    }
+}
+
+/// All particle effects, by id
+#[derive(Debug, Clone, Default, Deserialize, Resource)]
+pub struct ParticlesConfig(HashMap<String, ParticleEffectConfig>);
+
+#[derive(Debug, Clone, Deserialize)]
+struct ParticleEffectConfig {
+    /// particles per burst
+    #[serde(default)]
+    count: u32,
+    /// particles per second from an emitter
+    #[serde(default)]
+    rate: f32,
+    /// seconds that each particle lives for
+    lifetime: f32,
+    /// side length of square particles, before scaling
+    #[serde(default)]
+    size: f32,
+    /// image ids to pick from at random.  Particles are squares without any.
+    #[serde(default)]
+    sprites: Vec<String>,
+    /// particle speeds are picked between these two
+    speed: [f32; 2],
+    /// angle in radians that particles spread over, around the effect's direction
+    cone: f32,
+    /// fraction of the source's velocity that particles keep
+    #[serde(default)]
+    inherit_velocity: f32,
+    /// fastest that particles spin in radians per second, either way
+    #[serde(default)]
+    spin: f32,
+    /// hex colors that particles fade through over their life, evenly spaced
+    colors: Vec<String>,
+    /// scales that particles go through over their life, evenly spaced
+    scales: Vec<f32>,
+}
+
+/// Request to fire a particle effect once
+#[derive(Debug, Clone)]
+pub struct ParticleBurst {
+    /// key of the effect in the particles config
+    pub effect: String,
+    pub position: Vec3,
+    /// direction that the effect's cone points in
+    pub direction: Vec2,
+    /// velocity of whatever caused the effect
+    pub velocity: Vec2,
+}
+
+impl ParticleBurst {
+    pub fn new(effect: &str, position: Vec3) -> Self {
+        Self {
+            effect: String::from(effect),
+            position,
+            direction: Vec2::Y,
+            velocity: Vec2::ZERO,
+        }
+    }
+
+    pub fn with_direction(self, direction: Vec2) -> Self {
+        Self { direction, ..self }
+    }
+
+    pub fn with_velocity(self, velocity: Vec2) -> Self {
+        Self { velocity, ..self }
+    }
+}
+
+/// Continuously emits a particle effect from an entity while active
+#[derive(Debug, Clone, Component)]
+pub struct ParticleEmitter {
+    /// key of the effect in the particles config
+    pub effect: String,
+    pub active: bool,
+    /// position relative to the entity, which rotates with it
+    pub offset: Vec2,
+    /// direction relative to the entity, which rotates with it
+    pub direction: Vec2,
+    /// particles owed from previous frames
+    pending: f32,
+}
+
+impl ParticleEmitter {
+    pub fn new(effect: &str, offset: Vec2, direction: Vec2) -> Self {
+        Self {
+            effect: String::from(effect),
+            active: false,
+            offset,
+            direction,
+            pending: 0.,
+        }
+    }
+}
+
+/// Sets off explosions, which come with a sound.  Systems can also send other bursts and sounds
+/// through it.
+#[derive(SystemParam)]
+pub struct Explosions<'w> {
+    pub particles: EventWriter<'w, ParticleBurst>,
+    pub sounds: EventWriter<'w, SoundEvent>,
+}
+
+impl Explosions<'_> {
+    /// An explosion at `position`, drifting along with `velocity`
+    pub fn explode(&mut self, position: Vec3, velocity: Vec2) {
+        self.particles
+            .send(ParticleBurst::new("explosion", position).with_velocity(velocity));
+        self.sounds.send(SoundEvent::new("explosion"));
+    }
+}
+
+/// Particle effects, and the images that they use
+#[derive(SystemParam)]
+struct ParticleAssets<'w> {
+    config: Res<'w, ParticlesConfig>,
+    images: Res<'w, AssetMap<Image>>,
+}
+
+/// An emitter, along with what decides where its particles go
+type EmitterItem<'a> = (
+    &'a mut ParticleEmitter,
+    &'a Transform,
+    Option<&'a Velocity>,
+    Option<&'a Visibility>,
+);
+
+/// The parts of a particle that are reset when it is reused
+type PooledParticle<'a> = (
+    &'a mut Particle,
+    &'a mut Transform,
+    &'a mut Handle<Image>,
+    &'a mut Visibility,
+);
+
+/// Entities of particles that can be reused
+#[derive(Debug, Default, Resource)]
+struct ParticlePool {
+    free: Vec<Entity>,
+    /// particles spawned so far, alive or not
+    total: usize,
+}
+
+#[derive(Debug, Default, Component)]
+pub struct Particle {
+    active: bool,
+    velocity: Vec2,
+    angular_velocity: f32,
+    /// whether the particle is an image, which is scaled as is, rather than a square
+    textured: bool,
+    age: f32,
+    lifetime: f32,
+    size: f32,
+    colors: Vec<Color>,
+    scales: Vec<f32>,
+}
+
+impl Particle {
+    fn system_spawn(
+        mut commands: Commands,
+        mut evr_bursts: EventReader<ParticleBurst>,
+        mut q_emitters: Query<EmitterItem, Without<Particle>>,
+        mut q_particles: Query<PooledParticle>,
+        mut pool: ResMut<ParticlePool>,
+        assets: ParticleAssets,
+        time: Res<Time>,
+    ) {
+        let ParticleAssets { config, images } = &assets;
+        // particles are only for show, so they don't draw from the seeded game rng
+        let mut rng = thread_rng();
+        // gather bursts from events and emitters alike
+        let mut bursts: Vec<(ParticleBurst, u32)> = Vec::new();
+        for burst in evr_bursts.iter() {
+            let Some(effect) = config.0.get(&burst.effect) else {
+                warn!("Unknown particle effect: {}", burst.effect);
+                continue;
+            };
+            bursts.push((burst.clone(), effect.count));
+        }
+        for (mut emitter, xform, vel, visibility) in q_emitters.iter_mut() {
+            // nothing comes out of hidden entities, like ships in hyperspace
+            if !emitter.active || visibility == Some(&Visibility::Hidden) {
+                emitter.pending = 0.;
+                continue;
+            }
+            let Some(effect) = config.0.get(&emitter.effect) else {
+                continue;
+            };
+            emitter.pending += effect.rate * time.delta_seconds();
+            let count = emitter.pending.floor();
+            emitter.pending -= count;
+            if count < 1. {
+                continue;
+            }
+            let position = xform.transform_point(Vec3::from((emitter.offset, 0.)));
+            let direction = xform.rotation.mul_vec3(Vec3::from((emitter.direction, 0.)));
+            let burst = ParticleBurst::new(&emitter.effect, position)
+                .with_direction(direction.xy())
+                .with_velocity(vel.map_or(Vec2::ZERO, |vel| vel.0));
+            bursts.push((burst, count as u32));
+        }
+
+        for (burst, count) in bursts {
+            let effect = &config.0[&burst.effect];
+            let colors: Vec<Color> = effect
+                .colors
+                .iter()
+                .map(|hex| Color::hex(hex).unwrap_or(Color::WHITE))
+                .collect();
+            let direction = burst.direction.try_normalize().unwrap_or(Vec2::Y);
+            let half_cone = effect.cone.min(PI * 2.) / 2.;
+            for _ in 0..count {
+                let angle = if half_cone > 0. {
+                    rng.sample(Uniform::new_inclusive(-half_cone, half_cone))
+                } else {
+                    0.
+                };
+                let speed = if effect.speed[1] > effect.speed[0] {
+                    rng.sample(Uniform::new(effect.speed[0], effect.speed[1]))
+                } else {
+                    effect.speed[0]
+                };
+                let velocity = burst.velocity * effect.inherit_velocity
+                    + Vec2::from_angle(angle).rotate(direction) * speed;
+                let angular_velocity = if effect.spin > 0. {
+                    rng.sample(Uniform::new_inclusive(-effect.spin, effect.spin))
+                } else {
+                    0.
+                };
+                let texture = if effect.sprites.is_empty() {
+                    Handle::default()
+                } else {
+                    let id = &effect.sprites[rng.sample(Uniform::new(0, effect.sprites.len()))];
+                    images.0.get(id).cloned().unwrap_or_default()
+                };
+                let particle = Particle {
+                    active: true,
+                    velocity,
+                    angular_velocity,
+                    textured: !effect.sprites.is_empty(),
+                    age: 0.,
+                    lifetime: effect.lifetime,
+                    size: effect.size,
+                    colors: colors.clone(),
+                    scales: effect.scales.clone(),
+                };
+                let xform =
+                    Transform::from_translation(Vec3::from((burst.position.xy(), PARTICLE_Z)));
+
+                // reuse a pooled particle if there is one.  Entities that were despawned from
+                // under the pool are forgotten.
+                let mut reusable = None;
+                while let Some(entity) = pool.free.pop() {
+                    if q_particles.contains(entity) {
+                        reusable = Some(entity);
+                        break;
+                    }
+                    pool.total -= 1;
+                }
+                let reused = match reusable {
+                    Some(entity) => q_particles.get_mut(entity).ok(),
+                    None => None,
+                };
+                if let Some((mut old_particle, mut old_xform, mut old_texture, mut visibility)) =
+                    reused
+                {
+                    *old_particle = particle;
+                    *old_xform = xform;
+                    *old_texture = texture;
+                    *visibility = Visibility::Inherited;
+                } else if pool.total < MAX_PARTICLES {
+                    pool.total += 1;
+                    commands.spawn((
+                        particle,
+                        ViewportBounded,
+                        SpriteBundle {
+                            sprite: Sprite {
+                                color: colors.first().copied().unwrap_or(Color::WHITE),
+                                custom_size: Some(Vec2::ZERO),
+                                ..default()
+                            },
+                            texture,
+                            transform: xform,
+                            ..default()
+                        },
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Move particles and fade them through their colors and scales, then return them to the pool
+    fn system_update(
+        mut q: Query<(
+            Entity,
+            &mut Particle,
+            &mut Transform,
+            &mut Sprite,
+            &mut Visibility,
+        )>,
+        mut pool: ResMut<ParticlePool>,
+        time: Res<Time>,
+    ) {
+        let dt = time.delta_seconds();
+        for (entity, mut particle, mut xform, mut sprite, mut visibility) in q.iter_mut() {
+            if !particle.active {
+                continue;
+            }
+            particle.age += dt;
+            if particle.age >= particle.lifetime {
+                particle.active = false;
+                *visibility = Visibility::Hidden;
+                pool.free.push(entity);
+                continue;
+            }
+            let t = particle.age / particle.lifetime;
+            xform.translation += Vec3::from((particle.velocity * dt, 0.));
+            xform.rotation *= Quat::from_rotation_z(particle.angular_velocity * dt);
+            sprite.color = sample_colors(&particle.colors, t);
+            let scale = sample(&particle.scales, t);
+            if particle.textured {
+                sprite.custom_size = None;
+                xform.scale = Vec3::splat(scale);
+            } else {
+                sprite.custom_size = Some(Vec2::splat(particle.size * scale));
+            }
+        }
+    }
+
+    fn system_release_all(
+        mut q: Query<(Entity, &mut Particle, &mut Visibility)>,
+        mut pool: ResMut<ParticlePool>,
+    ) {
+        for (entity, mut particle, mut visibility) in q.iter_mut() {
+            if particle.active {
+                particle.active = false;
+                *visibility = Visibility::Hidden;
+                pool.free.push(entity);
+            }
+        }
+    }
+}
+
+/// Where `t` falls between evenly spaced keys, as the index of the key before it and the
+/// fraction of the way to the next one.
+fn key_position(len: usize, t: f32) -> (usize, f32) {
+    let x = t.clamp(0., 1.) * (len - 1) as f32;
+    let i = (x.floor() as usize).min(len - 2);
+    (i, x - i as f32)
+}
+
+fn sample(keys: &[f32], t: f32) -> f32 {
+    match keys.len() {
+        0 => 1.,
+        1 => keys[0],
+        len => {
+            let (i, f) = key_position(len, t);
+            keys[i] + (keys[i + 1] - keys[i]) * f
+        }
+    }
+}
+
+fn sample_colors(keys: &[Color], t: f32) -> Color {
+    match keys.len() {
+        0 => Color::WHITE,
+        1 => keys[0],
+        len => {
+            let (i, f) = key_position(len, t);
+            let [r0, g0, b0, a0] = keys[i].as_rgba_f32();
+            let [r1, g1, b1, a1] = keys[i + 1].as_rgba_f32();
+            Color::rgba(
+                r0 + (r1 - r0) * f,
+                g0 + (g1 - g0) * f,
+                b0 + (b1 - b0) * f,
+                a0 + (a1 - a0) * f,
+            )
+        }
+    }
+}
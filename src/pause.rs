@@ -0,0 +1,243 @@
+//! Pausing the game, and the pause menu.
+//!
+//! Gameplay systems only run in `AppState::InGame`, so everything freezes in place while paused.
+//! Pausing and resuming don't start or end a game, see `starting_new_game` and `ending_game`.
+
+use bevy::{
+    input::ButtonState,
+    prelude::{
+        default, AssetServer, Color, Commands, Component, Entity, EventReader, Handle,
+        IntoSystemAppConfig, IntoSystemConfig, MouseButton, NextState, OnEnter, OnExit, OnUpdate,
+        Plugin, Query, Res, ResMut, State, Transform, Vec2, Vec3, With,
+    },
+    sprite::{Sprite, SpriteBundle},
+    text::{Font, Text, Text2dBundle, TextStyle},
+};
+
+use crate::{
+    app::{game_in_progress, AppState, Resuming},
+    collision::Collider,
+    input::{ClickListener, InputAction, InputEvent},
+    shape::ColliderShape,
+    sounds::SoundOn,
+    viewport::ViewportBounds,
+};
+
+/// Size of the clickable area around each menu item
+const MENU_ITEM_SIZE: Vec2 = Vec2::new(640., 96.);
+const MENU_ITEM_SPACING: f32 = 128.;
+
+#[derive(Debug)]
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.init_resource::<Resuming>();
+        // a single system for both states, so that the same key press can't toggle twice
+        app.add_system(Self::system_handle_pause_input.run_if(game_in_progress));
+        app.add_system(Self::system_setup.in_schedule(OnEnter(AppState::Paused)));
+        app.add_system(Self::system_handle_click.in_set(OnUpdate(AppState::Paused)));
+        app.add_system(Self::system_cleanup.in_schedule(OnExit(AppState::Paused)));
+        app.add_system(Self::system_set_resuming.in_schedule(OnExit(AppState::Paused)));
+        app.add_system(Self::system_clear_resuming.in_schedule(OnExit(AppState::InGame)));
+    }
+}
+
+impl PausePlugin {
+    fn system_handle_pause_input(
+        mut evr_inputs: EventReader<InputEvent>,
+        state: Res<State<AppState>>,
+        mut next_state: ResMut<NextState<AppState>>,
+    ) {
+        let pressed = evr_inputs
+            .iter()
+            .any(|ev| matches!(ev.action, InputAction::Pause) && ev.state == ButtonState::Pressed);
+        if !pressed {
+            return;
+        }
+        match state.0 {
+            AppState::InGame => next_state.set(AppState::Paused),
+            AppState::Paused => next_state.set(AppState::InGame),
+            _ => {}
+        }
+    }
+
+    fn system_setup(
+        mut commands: Commands,
+        asset_server: Res<AssetServer>,
+        viewport_bounds: Res<ViewportBounds>,
+        sound_on: Option<Res<SoundOn>>,
+    ) {
+        // dim the frozen game behind the menu
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgba(0., 0., 0., 0.6),
+                    custom_size: Some(viewport_bounds.0.size()),
+                    ..default()
+                },
+                transform: Transform {
+                    translation: Vec3::new(0., 0., 10.),
+                    ..default()
+                },
+                ..default()
+            },
+            PauseCleanup,
+        ));
+
+        // spawn title text
+        let font = asset_server.load("fira_sans/FiraSans-Regular.ttf");
+        let title_text_style = TextStyle {
+            font_size: 128.0,
+            color: Color::WHITE,
+            font: font.clone(),
+        };
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section("Paused", title_text_style),
+                transform: Transform {
+                    translation: Vec3::new(0., 384., 11.),
+                    ..default()
+                },
+                ..default()
+            },
+            PauseCleanup,
+        ));
+
+        let sound_on = sound_on.is_none_or(|sound_on| sound_on.0);
+        spawn_menu_items(&mut commands, &font, PauseMenuItem::MAIN_PAGE, sound_on);
+    }
+
+    fn system_handle_click(
+        mut commands: Commands,
+        mut q_items: Query<(Entity, &PauseMenuItem, &mut ClickListener, &mut Text)>,
+        asset_server: Res<AssetServer>,
+        mut sound_on: Option<ResMut<SoundOn>>,
+        mut next_state: ResMut<NextState<AppState>>,
+    ) {
+        let clicked: Vec<PauseMenuItem> = q_items
+            .iter_mut()
+            .filter_map(|(_, item, mut listener, _)| {
+                let mut events = listener.0.drain();
+                events
+                    .any(|ev| ev.just_pressed(MouseButton::Left))
+                    .then_some(*item)
+            })
+            .collect();
+
+        for item in clicked {
+            match item {
+                PauseMenuItem::Resume => next_state.set(AppState::InGame),
+                PauseMenuItem::Quit => next_state.set(AppState::GameOver),
+                PauseMenuItem::ToggleSound => {
+                    if let Some(sound_on) = sound_on.as_mut() {
+                        sound_on.0 = !sound_on.0;
+                        for (_, item, _, mut text) in q_items.iter_mut() {
+                            if *item == PauseMenuItem::ToggleSound {
+                                text.sections[0].value = item.label(sound_on.0);
+                            }
+                        }
+                    }
+                }
+                PauseMenuItem::Settings | PauseMenuItem::Back => {
+                    // switch pages
+                    q_items
+                        .iter()
+                        .for_each(|(e, _, _, _)| commands.entity(e).despawn());
+                    let page = match item {
+                        PauseMenuItem::Settings => PauseMenuItem::SETTINGS_PAGE,
+                        _ => PauseMenuItem::MAIN_PAGE,
+                    };
+                    let font = asset_server.load("fira_sans/FiraSans-Regular.ttf");
+                    let sound_on = sound_on.as_ref().is_none_or(|sound_on| sound_on.0);
+                    spawn_menu_items(&mut commands, &font, page, sound_on);
+                }
+            }
+        }
+    }
+
+    fn system_cleanup(mut commands: Commands, q: Query<Entity, With<PauseCleanup>>) {
+        q.iter().for_each(|e| commands.entity(e).despawn());
+    }
+
+    fn system_set_resuming(mut resuming: ResMut<Resuming>, state: Res<State<AppState>>) {
+        resuming.0 = state.0 == AppState::InGame;
+    }
+
+    fn system_clear_resuming(mut resuming: ResMut<Resuming>) {
+        resuming.0 = false;
+    }
+}
+
+/// Spawn a page of menu items, from top to bottom.
+fn spawn_menu_items(
+    commands: &mut Commands,
+    font: &Handle<Font>,
+    items: &[PauseMenuItem],
+    sound_on: bool,
+) {
+    let text_style = TextStyle {
+        font_size: 64.0,
+        color: Color::WHITE,
+        font: font.clone(),
+    };
+    let half_size = MENU_ITEM_SIZE / 2.;
+    for (i, item) in items.iter().enumerate() {
+        let y = 128. - MENU_ITEM_SPACING * i as f32;
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(item.label(sound_on), text_style.clone()),
+                transform: Transform {
+                    translation: Vec3::new(0., y, 11.),
+                    ..default()
+                },
+                ..default()
+            },
+            ClickListener::default(),
+            Collider {
+                shape: ColliderShape::Polygon {
+                    points: vec![
+                        Vec2::new(-half_size.x, -half_size.y),
+                        Vec2::new(half_size.x, -half_size.y),
+                        Vec2::new(half_size.x, half_size.y),
+                        Vec2::new(-half_size.x, half_size.y),
+                    ],
+                },
+                ..default()
+            },
+            *item,
+            PauseCleanup,
+        ));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+pub enum PauseMenuItem {
+    Resume,
+    Settings,
+    Quit,
+    ToggleSound,
+    Back,
+}
+
+impl PauseMenuItem {
+    const MAIN_PAGE: &[PauseMenuItem] = &[Self::Resume, Self::Settings, Self::Quit];
+    const SETTINGS_PAGE: &[PauseMenuItem] = &[Self::ToggleSound, Self::Back];
+
+    fn label(&self, sound_on: bool) -> String {
+        let label = match self {
+            Self::Resume => "Resume",
+            Self::Settings => "Settings",
+            Self::Quit => "Quit",
+            Self::ToggleSound if sound_on => "Sound: On",
+            Self::ToggleSound => "Sound: Off",
+            Self::Back => "Back",
+        };
+        String::from(label)
+    }
+}
+
+// entities with this component will be cleaned up when the game is resumed or
+// quit.
+#[derive(Debug, Component)]
+pub struct PauseCleanup;
@@ -1,42 +1,58 @@
 use bevy::{
+    ecs::system::SystemParam,
     input::ButtonState,
+    math::Vec3Swizzles,
     prelude::{
-        App, Assets, Bundle, Commands, Component, EventReader, Handle, Image, IntoSystemAppConfig,
-        IntoSystemConfig, OnEnter, OnUpdate, Plugin, Query, Res, ResMut, Resource, With,
+        info, App, Bundle, Commands, Component, Entity, EventReader, EventWriter, Image,
+        IntoSystemAppConfig, IntoSystemConfig, NextState, OnEnter, OnUpdate, Plugin, Query, Res,
+        ResMut, Resource, Transform, Vec2, Vec3, Visibility, With, Without,
     },
     sprite::SpriteBundle,
-    time::{Timer, TimerMode},
-    utils::default,
+    time::{Time, Timer, TimerMode},
+    utils::{default, HashSet},
 };
-use bevy_kira_audio::{Audio, AudioControl, AudioInstance, AudioSource, AudioTween};
+use rand::{distributions::Uniform, Rng};
 use serde::Deserialize;
 
 use crate::{
-    app::AppState,
-    collision::Collider,
+    app::{starting_new_game, AppState, InGameCleanup},
+    collision::{Collider, CollisionLayers, SpatialGrid},
+    hyperspace::{HyperspaceCooldown, InHyperspace},
     input::{InputAction, InputEvent},
-    kinematics::{AngularDamping, KinematicsBundle, LinearDamping},
+    kinematics::{AngularDamping, KinematicsBundle, LinearDamping, Velocity},
     loading::AssetMap,
-    ship::{ShipBundle, ShipConfig, ShipControls, ShootCooldown},
-    viewport::ViewportBounded,
+    particle::{ParticleBurst, ParticleEmitter},
+    rng::GameRng,
+    ship::{Boost, Energy, Shield, ShipBundle, ShipConfig, ShipControls},
+    sounds::SoundEvent,
+    viewport::{ViewportBounded, ViewportBounds},
+    weapon::WeaponState,
 };
 
+/// How many random positions to try per frame when looking for a safe respawn position
+const RESPAWN_ATTEMPTS: usize = 16;
+
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(system_spawn.in_schedule(OnEnter(AppState::InGame)));
+        app.add_event::<PlayerDied>();
+        app.add_system(
+            system_spawn
+                .run_if(starting_new_game)
+                .in_schedule(OnEnter(AppState::InGame)),
+        );
+        app.add_system(system_release_controls.in_schedule(OnEnter(AppState::Paused)));
         app.add_system(system_handle_input.in_set(OnUpdate(AppState::InGame)));
-        app.add_system(system_thruster_sound.in_set(OnUpdate(AppState::InGame)));
+        app.add_system(system_handle_death.in_set(OnUpdate(AppState::InGame)));
+        app.add_system(system_respawn.in_set(OnUpdate(AppState::InGame)));
+        app.add_system(system_invulnerability.in_set(OnUpdate(AppState::InGame)));
     }
 }
 
 #[derive(Component, Default, Debug)]
 pub struct PlayerMarker;
 
-#[derive(Resource, Default, Debug)]
-pub struct ThrusterSound(Handle<AudioInstance>);
-
 #[derive(Component, Default, Debug)]
 pub struct PlayerInputMemory {
     pub thrust: Option<InputEvent>,
@@ -45,12 +61,60 @@ pub struct PlayerInputMemory {
 
 #[derive(Clone, Debug, Deserialize, Resource)]
 pub struct PlayerConfig {
+    /// ships per game, including the first one
+    pub lives: u32,
+    /// seconds between dying and respawning
+    pub respawn_delay: f32,
+    /// distance to keep from anything that could hit the player when respawning
+    pub respawn_clearance: f32,
+    /// seconds that a respawned player can't be hit
+    pub invulnerability: f32,
+    /// layers that an invulnerable player still collides with, since they can't hurt it
+    pub invulnerable_mask: CollisionLayers,
+    /// seconds between blinks while invulnerable
+    pub blink_interval: f32,
     pub ship: ShipConfig,
 }
 
+/// Sent when the player is hit by something deadly.
+#[derive(Debug, Clone, Copy)]
+pub struct PlayerDied(pub Entity);
+
+/// Ships left, including the current one
+#[derive(Debug, Resource)]
+pub struct PlayerLives(pub u32);
+
+/// Counts down to respawning the player, or to the game ending if it is out of lives
+#[derive(Debug, Resource)]
+pub struct RespawnTimer(Timer);
+
+/// Keeps a respawned player from being hit for a while, blinking to show it.  The deadly layers
+/// are taken out of the collider's mask until it wears off, so it can still pick up power-ups.
+#[derive(Component, Debug)]
+pub struct Invulnerable {
+    timer: Timer,
+    blink: Timer,
+    /// layers taken out of the mask, to be put back when it wears off
+    removed: CollisionLayers,
+}
+
+impl Invulnerable {
+    /// Make a collider invulnerable, keeping only the layers that are safe to touch.
+    fn apply(config: &PlayerConfig, collider: &mut Collider) -> Self {
+        let removed = collider.mask.difference(config.invulnerable_mask);
+        collider.mask = collider.mask.difference(removed);
+        Self {
+            timer: Timer::from_seconds(config.invulnerability, TimerMode::Once),
+            blink: Timer::from_seconds(config.blink_interval, TimerMode::Repeating),
+            removed,
+        }
+    }
+}
+
 #[derive(Bundle)]
 pub struct PlayerBundle {
     marker: PlayerMarker,
+    cleanup: InGameCleanup,
     input_memory: PlayerInputMemory,
     viewport_bounded: ViewportBounded,
 
@@ -58,64 +122,209 @@ pub struct PlayerBundle {
     ship: ShipBundle,
 }
 
+impl PlayerBundle {
+    pub fn from_config(
+        config: &PlayerConfig,
+        loaded_images: &AssetMap<Image>,
+        position: Vec2,
+    ) -> Self {
+        // TODO: get ShipBundle from ShipConfig
+        let sprite_id = &config.ship.sprite_id;
+        let err_msg = format!("Could not find player sprite: {}", sprite_id);
+        let sprite_tex = loaded_images.0.get(sprite_id).expect(&err_msg).clone();
+        PlayerBundle {
+            marker: PlayerMarker,
+            cleanup: InGameCleanup,
+            input_memory: PlayerInputMemory::default(),
+            viewport_bounded: ViewportBounded,
+            ship: ShipBundle {
+                controls: ShipControls::default(),
+                config: config.ship.clone(),
+                collider: Collider {
+                    shape: config.ship.collision_shape.clone(),
+                    layers: config.ship.collision_layers,
+                    mask: config.ship.collision_mask,
+                },
+                weapon: WeaponState::default(),
+                hyperspace_cooldown: HyperspaceCooldown::new(config.ship.hyperspace_cooldown),
+                energy: Energy(config.ship.energy_capacity),
+                shield: Shield::default(),
+                boost: Boost::default(),
+                exhaust: ParticleEmitter::new(
+                    "thruster",
+                    Vec2::new(0., -config.ship.exhaust_offset),
+                    Vec2::NEG_Y,
+                ),
+                sprite: SpriteBundle {
+                    texture: sprite_tex,
+                    transform: Transform::from_translation(Vec3::from((position, 0.))),
+                    ..default()
+                },
+                kinematics: KinematicsBundle {
+                    linear_damping: LinearDamping(config.ship.velocity_damping),
+                    angular_damping: AngularDamping(config.ship.rotation_rate_damping),
+                    ..default()
+                },
+            },
+        }
+    }
+}
+
 pub fn system_spawn(
     mut commands: Commands,
     loaded_images: Res<AssetMap<Image>>,
     config: Res<PlayerConfig>,
-    loaded_audio: Res<AssetMap<AudioSource>>,
-    audio: Res<Audio>,
-    mut audio_instances: ResMut<Assets<AudioInstance>>,
 ) {
-    // TODO: get ShipBundle from ShipConfig
-    let sprite_id = &config.ship.sprite_id;
-    let err_msg = format!("Could not find player sprite: {}", sprite_id);
-    let sprite_tex = loaded_images.0.get(sprite_id).expect(&err_msg).clone();
-    let player = PlayerBundle {
-        marker: PlayerMarker,
-        input_memory: PlayerInputMemory::default(),
-        viewport_bounded: ViewportBounded,
-        ship: ShipBundle {
-            controls: ShipControls::default(),
-            config: config.ship.clone(),
-            collider: Collider {
-                radius: config.ship.collision_radius,
-            },
-            shoot_cooldown: ShootCooldown(Timer::from_seconds(
-                config.ship.shoot_cooldown,
-                TimerMode::Once,
-            )),
-            sprite: SpriteBundle {
-                texture: sprite_tex,
-                ..default()
-            },
-            kinematics: KinematicsBundle {
-                linear_damping: LinearDamping(config.ship.velocity_damping),
-                angular_damping: AngularDamping(config.ship.rotation_rate_damping),
-                ..default()
-            },
-        },
-    };
-    commands.spawn(player);
-    // thruster sound
-    let sound = loaded_audio.0.get("thruster").unwrap();
-    let handle = audio.play(sound.clone()).looped().handle();
-    commands.insert_resource(ThrusterSound(handle.clone()));
-    if let Some(instance) = audio_instances.get_mut(&handle) {
-        instance.set_volume(0., AudioTween::default());
+    commands.insert_resource(PlayerLives(config.lives));
+    commands.remove_resource::<RespawnTimer>();
+    commands.spawn(PlayerBundle::from_config(
+        &config,
+        &loaded_images,
+        Vec2::ZERO,
+    ));
+}
+
+/// Let go of all controls when pausing, since key releases during the pause are missed.
+pub fn system_release_controls(
+    mut q: Query<(&mut ShipControls, &mut PlayerInputMemory), With<PlayerMarker>>,
+) {
+    for (mut controls, mut input_mem) in q.iter_mut() {
+        *controls = ShipControls::default();
+        *input_mem = PlayerInputMemory::default();
     }
 }
 
-pub fn system_thruster_sound(
-    thruster_sound: Res<ThrusterSound>,
-    mut audio_instances: ResMut<Assets<AudioInstance>>,
-    q: Query<&ShipControls, With<PlayerMarker>>,
+pub fn system_handle_death(
+    mut commands: Commands,
+    mut evr_died: EventReader<PlayerDied>,
+    q_player: Query<(&Transform, &Velocity), With<PlayerMarker>>,
+    mut lives: ResMut<PlayerLives>,
+    config: Res<PlayerConfig>,
+    mut evw_sounds: EventWriter<SoundEvent>,
+    mut evw_particles: EventWriter<ParticleBurst>,
 ) {
-    if q.is_empty() {
+    // several things can kill the player at once, but it only dies once
+    let mut dead: HashSet<Entity> = HashSet::default();
+    for PlayerDied(entity) in evr_died.iter() {
+        let Ok((xform, vel)) = q_player.get(*entity) else {
+            continue;
+        };
+        if !dead.insert(*entity) {
+            continue;
+        }
+        commands.entity(*entity).despawn();
+        evw_sounds.send(SoundEvent::new("explosion"));
+        evw_particles
+            .send(ParticleBurst::new("player_death", xform.translation).with_velocity(vel.0));
+        lives.0 = lives.0.saturating_sub(1);
+        commands.insert_resource(RespawnTimer(Timer::from_seconds(
+            config.respawn_delay,
+            TimerMode::Once,
+        )));
+    }
+}
+
+/// Respawn the player once the delay is up, and there is a safe place for it.  Until then this
+/// keeps looking every frame.  Without lives left, the game is over instead.
+pub fn system_respawn(
+    mut commands: Commands,
+    respawn_timer: Option<ResMut<RespawnTimer>>,
+    time: Res<Time>,
+    lives: Res<PlayerLives>,
+    mut next_state: ResMut<NextState<AppState>>,
+    loaded_images: Res<AssetMap<Image>>,
+    mut spots: RespawnSpots,
+) {
+    let Some(mut respawn_timer) = respawn_timer else {
+        return;
+    };
+    if !respawn_timer.0.tick(time.delta()).finished() {
+        return;
+    }
+    if lives.0 == 0 {
+        info!("Out of lives");
+        commands.remove_resource::<RespawnTimer>();
+        next_state.set(AppState::GameOver);
         return;
     }
-    let controls = q.single();
-    if let Some(instance) = audio_instances.get_mut(&thruster_sound.0) {
-        instance.set_volume(controls.thrust as f64, AudioTween::default());
+
+    let Some(position) = spots.find() else {
+        return;
+    };
+
+    commands.remove_resource::<RespawnTimer>();
+    let config = &spots.config;
+    let mut player = PlayerBundle::from_config(config, &loaded_images, position);
+    let invulnerable = Invulnerable::apply(config, &mut player.ship.collider);
+    commands.spawn((player, invulnerable));
+}
+
+/// Looks for places to respawn the player, away from anything that could hit it
+#[derive(SystemParam)]
+pub struct RespawnSpots<'w, 's> {
+    config: Res<'w, PlayerConfig>,
+    grid: Res<'w, SpatialGrid>,
+    q_colliders: Query<'w, 's, (&'static Transform, &'static Collider)>,
+    bounds: Res<'w, ViewportBounds>,
+    rng: ResMut<'w, GameRng>,
+}
+
+impl RespawnSpots<'_, '_> {
+    /// A safe position, preferring the center like at the start of the game.  Only a few random
+    /// positions are tried, so this may find nothing.
+    fn find(&mut self) -> Option<Vec2> {
+        if self.is_safe(Vec2::ZERO) {
+            return Some(Vec2::ZERO);
+        }
+        let x_dist = Uniform::new(self.bounds.0.min.x, self.bounds.0.max.x);
+        let y_dist = Uniform::new(self.bounds.0.min.y, self.bounds.0.max.y);
+        (0..RESPAWN_ATTEMPTS).find_map(|_| {
+            let pos = Vec2::new(self.rng.rng.sample(x_dist), self.rng.rng.sample(y_dist));
+            self.is_safe(pos).then_some(pos)
+        })
+    }
+
+    /// Whether nothing the ship collides with is within the respawn clearance of a position
+    fn is_safe(&self, pos: Vec2) -> bool {
+        let clearance = self.config.respawn_clearance;
+        let mask = self.config.ship.collision_mask;
+        self.grid.query(pos, clearance).into_iter().all(|entity| {
+            let Ok((xform, collider)) = self.q_colliders.get(entity) else {
+                return true;
+            };
+            let distance = self
+                .bounds
+                .wrap_delta(xform.translation.xy() - pos)
+                .length();
+            !mask.intersects(collider.layers)
+                || distance - collider.shape.bounding_radius() > clearance
+        })
+    }
+}
+
+/// Blink invulnerable players, and make them vulnerable again when it wears off.
+pub fn system_invulnerability(
+    mut commands: Commands,
+    mut q: Query<
+        (Entity, &mut Invulnerable, &mut Visibility, &mut Collider),
+        // blinking would reveal a ship in hyperspace
+        Without<InHyperspace>,
+    >,
+    time: Res<Time>,
+) {
+    for (entity, mut invulnerable, mut visibility, mut collider) in q.iter_mut() {
+        if invulnerable.timer.tick(time.delta()).finished() {
+            *visibility = Visibility::Inherited;
+            collider.mask = collider.mask.union(invulnerable.removed);
+            commands.entity(entity).remove::<Invulnerable>();
+            continue;
+        }
+        if invulnerable.blink.tick(time.delta()).just_finished() {
+            *visibility = match *visibility {
+                Visibility::Hidden => Visibility::Inherited,
+                _ => Visibility::Hidden,
+            };
+        }
     }
 }
 
@@ -132,10 +341,8 @@ pub fn system_handle_input(
                         controls.thrust = x.clamp(-1., 1.);
                         input_mem.thrust = Some(*ev_input);
                     }
-                    (ButtonState::Released, Some(prev)) => {
-                        if ev_input.scan_code == prev.scan_code {
-                            controls.thrust = 0.;
-                        }
+                    (ButtonState::Released, Some(prev)) if ev_input.scan_code == prev.scan_code => {
+                        controls.thrust = 0.;
                     }
                     _ => {}
                 };
@@ -148,10 +355,8 @@ pub fn system_handle_input(
                     controls.turn = x.clamp(-1., 1.);
                     input_mem.turn = Some(*ev_input);
                 }
-                (ButtonState::Released, Some(prev)) => {
-                    if ev_input.scan_code == prev.scan_code {
-                        controls.turn = 0.
-                    }
+                (ButtonState::Released, Some(prev)) if ev_input.scan_code == prev.scan_code => {
+                    controls.turn = 0.
                 }
                 _ => {}
             },
@@ -159,6 +364,20 @@ pub fn system_handle_input(
                 ButtonState::Pressed => controls.shoot = true,
                 ButtonState::Released => controls.shoot = false,
             },
+            InputAction::Shield => match ev_input.state {
+                ButtonState::Pressed => controls.shield = true,
+                ButtonState::Released => controls.shield = false,
+            },
+            InputAction::Boost => match ev_input.state {
+                ButtonState::Pressed => controls.boost = true,
+                ButtonState::Released => controls.boost = false,
+            },
+            InputAction::Hyperspace => match ev_input.state {
+                ButtonState::Pressed => controls.hyperspace = true,
+                ButtonState::Released => controls.hyperspace = false,
+            },
+            // handled by the pause module
+            InputAction::Pause => {}
         });
     }
 }
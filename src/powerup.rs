@@ -0,0 +1,362 @@
+//! Power-ups, which meteors drop by chance when they are shot.
+//!
+//! Pickups drift around for a while before they disappear.  When the player's ship touches one,
+//! its effect is applied to the ship, and most effects wear off after some time.
+
+use std::{collections::BTreeMap, f32::consts::PI};
+
+use bevy::{
+    prelude::{
+        Color, Commands, Component, Entity, EventReader, IntoSystemConfig, OnUpdate, Plugin, Query,
+        Res, ResMut, Resource, Transform, Vec2, With,
+    },
+    sprite::{Sprite, SpriteBundle},
+    time::{Time, Timer, TimerMode},
+    utils::{default, HashMap, HashSet},
+};
+use rand::{distributions::Uniform, Rng};
+use serde::Deserialize;
+
+use crate::{
+    app::{AppState, InGameCleanup},
+    collision::{Collider, CollisionLayers, CollisionStarted},
+    kinematics::{AngularVelocity, KinematicsBundle, Velocity},
+    meteor::{MeteorDestroyed, MeteorSize},
+    player::{system_handle_death, PlayerLives, PlayerMarker},
+    rng::GameRng,
+    shape::ColliderShape,
+    viewport::ViewportBounded,
+};
+
+#[derive(Debug)]
+pub struct PowerupPlugin;
+
+impl Plugin for PowerupPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.add_system(Pickup::system_drop.in_set(OnUpdate(AppState::InGame)));
+        app.add_system(Pickup::system_lifetime.in_set(OnUpdate(AppState::InGame)));
+        // effects are inserted on the player, so this has to happen before it can be despawned
+        app.add_system(
+            Pickup::system_handle_collision
+                .before(system_handle_death)
+                .in_set(OnUpdate(AppState::InGame)),
+        );
+        app.add_system(system_expire::<RapidFire>.in_set(OnUpdate(AppState::InGame)));
+        app.add_system(system_expire::<SpreadShot>.in_set(OnUpdate(AppState::InGame)));
+        app.add_system(system_expire::<ShieldPowerup>.in_set(OnUpdate(AppState::InGame)));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+pub enum PowerupKind {
+    RapidFire,
+    SpreadShot,
+    Shield,
+    ExtraLife,
+}
+
+#[derive(Debug, Clone, Deserialize, Resource)]
+pub struct PowerupsConfig {
+    /// seconds before an uncollected pickup disappears
+    lifetime: f32,
+    /// drifting speed of pickups
+    speed: f32,
+    /// side length of pickups
+    size: f32,
+    collision_layers: CollisionLayers,
+    collision_mask: CollisionLayers,
+    /// pickup colors as hex strings, by kind
+    colors: HashMap<PowerupKind, String>,
+    /// chance of dropping each kind of power-up, by meteor size.  Kinds are kept in a stable
+    /// order, so that seeded games drop the same power-ups.
+    drops: HashMap<MeteorSize, BTreeMap<PowerupKind, f32>>,
+    rapid_fire: RapidFireConfig,
+    spread_shot: SpreadShotConfig,
+    shield: ShieldPowerupConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RapidFireConfig {
+    duration: f32,
+    /// multiplies the ship's shoot cooldown
+    cooldown_factor: f32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct SpreadShotConfig {
+    duration: f32,
+    /// id of the weapon to use while it lasts
+    weapon: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ShieldPowerupConfig {
+    duration: f32,
+}
+
+impl PowerupsConfig {
+    /// Id of the weapon that the spread shot power-up swaps in
+    pub fn spread_shot_weapon(&self) -> &String {
+        &self.spread_shot.weapon
+    }
+
+    /// Roll which power-up, if any, a meteor of this size drops.
+    fn roll_drop<R: Rng>(&self, rng: &mut R, size: MeteorSize) -> Option<PowerupKind> {
+        let table = self.drops.get(&size)?;
+        let roll = rng.gen::<f32>();
+        let mut cumulative = 0.;
+        table.iter().find_map(|(kind, chance)| {
+            cumulative += chance;
+            (roll < cumulative).then_some(*kind)
+        })
+    }
+}
+
+/// A power-up waiting to be picked up
+#[derive(Debug, Component)]
+pub struct Pickup {
+    kind: PowerupKind,
+    lifetime: Timer,
+}
+
+impl Pickup {
+    fn system_drop(
+        mut commands: Commands,
+        mut evr_destroyed: EventReader<MeteorDestroyed>,
+        config: Res<PowerupsConfig>,
+        mut rng: ResMut<GameRng>,
+    ) {
+        for ev in evr_destroyed.iter() {
+            let Some(kind) = config.roll_drop(&mut rng.rng, ev.size) else {
+                continue;
+            };
+            let angle = rng.rng.sample(Uniform::new(0., PI * 2.));
+            let color = config
+                .colors
+                .get(&kind)
+                .and_then(|hex| Color::hex(hex).ok())
+                .unwrap_or(Color::WHITE);
+            commands.spawn((
+                Pickup {
+                    kind,
+                    lifetime: Timer::from_seconds(config.lifetime, TimerMode::Once),
+                },
+                InGameCleanup,
+                Collider {
+                    shape: ColliderShape::Circle {
+                        radius: config.size / 2.,
+                    },
+                    layers: config.collision_layers,
+                    mask: config.collision_mask,
+                },
+                ViewportBounded,
+                SpriteBundle {
+                    sprite: Sprite {
+                        color,
+                        custom_size: Some(Vec2::splat(config.size)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(ev.position),
+                    ..default()
+                },
+                KinematicsBundle {
+                    velocity: Velocity(Vec2::from_angle(angle) * config.speed),
+                    angular_velocity: AngularVelocity(PI / 2.),
+                    ..default()
+                },
+            ));
+        }
+    }
+
+    fn system_lifetime(
+        mut commands: Commands,
+        mut q: Query<(Entity, &mut Pickup)>,
+        time: Res<Time>,
+    ) {
+        for (entity, mut pickup) in q.iter_mut() {
+            if pickup.lifetime.tick(time.delta()).finished() {
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+
+    fn system_handle_collision(
+        mut commands: Commands,
+        mut evr_collisions: EventReader<CollisionStarted>,
+        q_pickups: Query<&Pickup>,
+        q_player: Query<(), With<PlayerMarker>>,
+        config: Res<PowerupsConfig>,
+        mut lives: Option<ResMut<PlayerLives>>,
+    ) {
+        // a pickup can touch the player more than once before it is despawned
+        let mut collected: HashSet<Entity> = HashSet::default();
+        for ev in evr_collisions.iter() {
+            let Some((player_entity, pickup_entity)) = ev.ordered(|e| q_player.contains(e)) else {
+                continue;
+            };
+            let Ok(pickup) = q_pickups.get(pickup_entity) else {
+                continue;
+            };
+            if !collected.insert(pickup_entity) {
+                continue;
+            }
+            commands.entity(pickup_entity).despawn();
+            let mut player = commands.entity(player_entity);
+            match pickup.kind {
+                PowerupKind::RapidFire => {
+                    player.insert(RapidFire {
+                        timer: Timer::from_seconds(config.rapid_fire.duration, TimerMode::Once),
+                        cooldown_factor: config.rapid_fire.cooldown_factor,
+                    });
+                }
+                PowerupKind::SpreadShot => {
+                    player.insert(SpreadShot {
+                        timer: Timer::from_seconds(config.spread_shot.duration, TimerMode::Once),
+                        weapon: config.spread_shot.weapon.clone(),
+                    });
+                }
+                PowerupKind::Shield => {
+                    player.insert(ShieldPowerup(Timer::from_seconds(
+                        config.shield.duration,
+                        TimerMode::Once,
+                    )));
+                }
+                PowerupKind::ExtraLife => {
+                    if let Some(lives) = lives.as_mut() {
+                        lives.0 += 1;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// An effect on a ship that wears off after a while
+trait TimedPowerup: Component {
+    fn timer_mut(&mut self) -> &mut Timer;
+}
+
+fn system_expire<T: TimedPowerup>(
+    mut commands: Commands,
+    mut q: Query<(Entity, &mut T)>,
+    time: Res<Time>,
+) {
+    for (entity, mut powerup) in q.iter_mut() {
+        if powerup.timer_mut().tick(time.delta()).finished() {
+            commands.entity(entity).remove::<T>();
+        }
+    }
+}
+
+/// Shorter weapon cooldown
+#[derive(Debug, Component)]
+pub struct RapidFire {
+    timer: Timer,
+    pub cooldown_factor: f32,
+}
+
+impl TimedPowerup for RapidFire {
+    fn timer_mut(&mut self) -> &mut Timer {
+        &mut self.timer
+    }
+}
+
+/// A spread weapon in place of the ship's own
+#[derive(Debug, Component)]
+pub struct SpreadShot {
+    timer: Timer,
+    pub weapon: String,
+}
+
+impl TimedPowerup for SpreadShot {
+    fn timer_mut(&mut self) -> &mut Timer {
+        &mut self.timer
+    }
+}
+
+/// A shield that is up without spending energy
+#[derive(Debug, Component)]
+pub struct ShieldPowerup(Timer);
+
+impl TimedPowerup for ShieldPowerup {
+    fn timer_mut(&mut self) -> &mut Timer {
+        &mut self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    const CONFIG: &str = r#"
+        lifetime = 10.0
+        speed = 80.0
+        size = 40.0
+        collision_layers = ["Powerup"]
+        collision_mask = ["Player"]
+
+        [colors]
+
+        [drops.Large]
+        RapidFire = 0.25
+        ExtraLife = 0.25
+
+        [drops.Small]
+        Shield = 1.0
+
+        [rapid_fire]
+        duration = 1.0
+        cooldown_factor = 0.5
+
+        [spread_shot]
+        duration = 1.0
+        weapon = "spread"
+
+        [shield]
+        duration = 1.0
+    "#;
+
+    fn rolls(size: MeteorSize, count: usize, seed: u64) -> Vec<Option<PowerupKind>> {
+        let config: PowerupsConfig = toml::from_str(CONFIG).unwrap();
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..count)
+            .map(|_| config.roll_drop(&mut rng, size))
+            .collect()
+    }
+
+    #[test]
+    fn sizes_without_drops() {
+        assert!(rolls(MeteorSize::Medium, 100, 1)
+            .iter()
+            .all(Option::is_none));
+    }
+
+    #[test]
+    fn certain_drops() {
+        assert!(rolls(MeteorSize::Small, 100, 1)
+            .iter()
+            .all(|kind| *kind == Some(PowerupKind::Shield)));
+    }
+
+    #[test]
+    fn drop_chances() {
+        let drops = rolls(MeteorSize::Large, 10000, 1);
+        let share = |kind: Option<PowerupKind>| {
+            drops.iter().filter(|k| **k == kind).count() as f32 / drops.len() as f32
+        };
+        assert!((share(Some(PowerupKind::RapidFire)) - 0.25).abs() < 0.03);
+        assert!((share(Some(PowerupKind::ExtraLife)) - 0.25).abs() < 0.03);
+        assert!((share(None) - 0.5).abs() < 0.03);
+        assert_eq!(share(Some(PowerupKind::SpreadShot)), 0.);
+    }
+
+    #[test]
+    fn seeded_drops_are_reproducible() {
+        assert_eq!(
+            rolls(MeteorSize::Large, 100, 7),
+            rolls(MeteorSize::Large, 100, 7)
+        );
+    }
+}
@@ -2,7 +2,7 @@ use bevy::{
     math::Vec3Swizzles,
     prelude::{
         Bundle, Commands, Component, Entity, Image, IntoSystemConfig, OnUpdate, Plugin, Query, Res,
-        Resource, Transform, Vec3,
+        Transform, Vec2, Vec3,
     },
     sprite::SpriteBundle,
     time::{Time, Timer, TimerMode},
@@ -11,10 +11,11 @@ use bevy::{
 use serde::Deserialize;
 
 use crate::{
-    app::AppState,
-    collision::Collider,
+    app::{AppState, InGameCleanup},
+    collision::{Bullet, Collider, CollisionLayers},
     kinematics::{KinematicsBundle, Velocity},
     loading::AssetMap,
+    shape::ColliderShape,
     viewport::ViewportBounded,
 };
 
@@ -44,24 +45,48 @@ impl ProjectilePlugin {
     }
 }
 
-#[derive(Debug, Clone, Resource, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct ProjectileConfig {
     sprite_id: String,
     speed: f32,
-    collision_radius: f32,
+    /// collision shape in sprite pixels, before scaling
+    collision_shape: ColliderShape,
+    collision_layers: CollisionLayers,
+    collision_mask: CollisionLayers,
     scale: f32,
     lifetime: f32,
+    /// hit points taken off whatever the projectile hits
+    #[serde(default = "default_damage")]
+    damage: u32,
+}
+
+fn default_damage() -> u32 {
+    1
+}
+
+impl ProjectileConfig {
+    /// The same projectile, `factor` times as large and as damaging
+    pub fn scaled(&self, factor: f32) -> Self {
+        Self {
+            scale: self.scale * factor,
+            damage: (self.damage as f32 * factor).round() as u32,
+            ..self.clone()
+        }
+    }
 }
 
 #[derive(Debug, Clone, Component)]
 pub struct ProjectileComponent {
     lifetime: Timer,
+    pub damage: u32,
 }
 
 #[derive(Clone, Bundle)]
 pub struct ProjectileBundle {
     projectile: ProjectileComponent,
+    cleanup: InGameCleanup,
     collider: Collider,
+    bullet: Bullet,
     bounded: ViewportBounded,
     #[bundle]
     sprite: SpriteBundle,
@@ -70,9 +95,12 @@ pub struct ProjectileBundle {
 }
 
 impl ProjectileBundle {
+    /// A projectile fired in the direction `xform` is facing, carried along by the shooter's
+    /// velocity.
     pub fn from_config(
         config: &ProjectileConfig,
         xform: &Transform,
+        shooter_velocity: Vec2,
         sprites: &AssetMap<Image>,
     ) -> Self {
         let texture = sprites
@@ -81,14 +109,19 @@ impl ProjectileBundle {
             .unwrap_or_else(|| panic!("could not find sprite_id: ({})", config.sprite_id))
             .clone();
         let direction = xform.up().xy();
-        let velocity = Velocity(direction * config.speed);
+        let velocity = Velocity(shooter_velocity + direction * config.speed);
         Self {
             projectile: ProjectileComponent {
                 lifetime: Timer::from_seconds(config.lifetime, TimerMode::Once),
+                damage: config.damage,
             },
+            cleanup: InGameCleanup,
             collider: Collider {
-                radius: config.collision_radius,
+                shape: config.collision_shape.scaled(config.scale),
+                layers: config.collision_layers,
+                mask: config.collision_mask,
             },
+            bullet: Bullet,
             bounded: ViewportBounded,
             sprite: SpriteBundle {
                 texture,
@@ -0,0 +1,70 @@
+//! Seeded random number generation.
+//!
+//! Gameplay systems draw all of their randomness from `GameRng`, so that a seed always reproduces
+//! the same game.  This makes bug reports reproducible, and allows for shared challenges and
+//! replays.
+
+use bevy::prelude::{
+    info, IntoSystemAppConfig, IntoSystemConfig, OnEnter, Plugin, Res, ResMut, Resource,
+};
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+use serde::Deserialize;
+
+use crate::app::{starting_new_game, AppState, LaunchOptions};
+
+pub struct RngPlugin;
+
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.init_resource::<GameRng>();
+        app.add_system(
+            GameRng::system_reseed
+                .run_if(starting_new_game)
+                .in_schedule(OnEnter(AppState::InGame)),
+        );
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Default, Resource)]
+pub struct RngConfig {
+    /// Fixed seed for every game.  A random seed is rolled for each game when unset.
+    pub seed: Option<u64>,
+}
+
+/// The random number generator shared by all gameplay systems.
+#[derive(Debug, Clone, Resource)]
+pub struct GameRng {
+    /// seed that the generator was created from, for reproducing this game
+    pub seed: u64,
+    pub rng: StdRng,
+}
+
+impl GameRng {
+    pub fn from_seed(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Reseed the generator at the start of a game.  The launch options take precedence over
+    /// the config.
+    pub fn system_reseed(
+        mut rng: ResMut<GameRng>,
+        rng_config: Res<RngConfig>,
+        options: Option<Res<LaunchOptions>>,
+    ) {
+        let seed = options
+            .and_then(|options| options.seed)
+            .or(rng_config.seed)
+            .unwrap_or_else(|| thread_rng().gen());
+        *rng = Self::from_seed(seed);
+        info!("RNG seed: {}", rng.seed);
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::from_seed(thread_rng().gen())
+    }
+}
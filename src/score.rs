@@ -0,0 +1,148 @@
+//! Scoring, and the extra lives that come with it.
+//!
+//! Gameplay modules send `PointsScored` events, and this module keeps the `Score` up to date.
+//! Anything that shows or reacts to the score (HUD, audio, stats) should listen for
+//! `ScoreChanged` instead of polling the resource.
+
+use bevy::prelude::{
+    info, EventReader, EventWriter, IntoSystemAppConfig, IntoSystemConfig, OnEnter, OnUpdate,
+    Plugin, Res, ResMut, Resource,
+};
+use serde::Deserialize;
+
+use crate::{
+    app::{starting_new_game, AppState},
+    player::PlayerLives,
+};
+
+pub struct ScorePlugin;
+
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.init_resource::<Score>();
+        app.add_event::<PointsScored>();
+        app.add_event::<ScoreChanged>();
+        app.add_system(
+            Score::system_reset
+                .run_if(starting_new_game)
+                .in_schedule(OnEnter(AppState::InGame)),
+        );
+        app.add_system(Score::system_handle_points.in_set(OnUpdate(AppState::InGame)));
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Default, Resource)]
+pub struct ScoreConfig {
+    /// scores at which the player gets an extra life
+    #[serde(default)]
+    pub extra_life_at: Vec<u32>,
+    /// after the last of `extra_life_at`, award another extra life every this many points
+    pub extra_life_every: Option<u32>,
+}
+
+impl ScoreConfig {
+    /// Number of extra lives earned by going from the `from` score to the `to` score
+    fn extra_lives_between(&self, from: u32, to: u32) -> u32 {
+        let crossed = |threshold: u32| from < threshold && threshold <= to;
+        let mut lives = self.extra_life_at.iter().filter(|t| crossed(**t)).count() as u32;
+        if let Some(every) = self.extra_life_every.filter(|every| *every > 0) {
+            let start = self.extra_life_at.iter().copied().max().unwrap_or(0);
+            // count multiples of `every` past the last fixed threshold
+            let passed = |score: u32| score.saturating_sub(start) / every;
+            lives += passed(to) - passed(from);
+        }
+        lives
+    }
+}
+
+/// The current game's score
+#[derive(Debug, Default, Resource)]
+pub struct Score(pub u32);
+
+/// Sent by gameplay modules to award points to the player.
+#[derive(Debug, Clone, Copy)]
+pub struct PointsScored(pub u32);
+
+/// Sent whenever the score changes, including when it is reset for a new game.
+#[derive(Debug, Clone, Copy)]
+pub struct ScoreChanged {
+    pub score: u32,
+}
+
+impl Score {
+    fn system_reset(mut score: ResMut<Score>, mut evw_changed: EventWriter<ScoreChanged>) {
+        score.0 = 0;
+        evw_changed.send(ScoreChanged { score: 0 });
+    }
+
+    fn system_handle_points(
+        mut evr_points: EventReader<PointsScored>,
+        mut score: ResMut<Score>,
+        config: Res<ScoreConfig>,
+        lives: Option<ResMut<PlayerLives>>,
+        mut evw_changed: EventWriter<ScoreChanged>,
+    ) {
+        let points: u32 = evr_points.iter().map(|ev| ev.0).sum();
+        if points == 0 {
+            return;
+        }
+        let previous = score.0;
+        score.0 = score.0.saturating_add(points);
+        evw_changed.send(ScoreChanged { score: score.0 });
+
+        let extra_lives = config.extra_lives_between(previous, score.0);
+        if extra_lives == 0 {
+            return;
+        }
+        if let Some(mut lives) = lives {
+            lives.0 += extra_lives;
+            info!("Extra life at {} points, {} lives left", score.0, lives.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score_config(extra_life_at: Vec<u32>, extra_life_every: Option<u32>) -> ScoreConfig {
+        ScoreConfig {
+            extra_life_at,
+            extra_life_every,
+        }
+    }
+
+    #[test]
+    fn fixed_thresholds() {
+        let config = score_config(vec![5000, 20000], None);
+        assert_eq!(config.extra_lives_between(0, 4999), 0);
+        assert_eq!(config.extra_lives_between(4999, 5000), 1);
+        assert_eq!(config.extra_lives_between(5000, 5000), 0);
+        assert_eq!(config.extra_lives_between(5000, 19999), 0);
+        assert_eq!(config.extra_lives_between(0, 100000), 2);
+    }
+
+    #[test]
+    fn repeating_thresholds() {
+        let config = score_config(vec![10000], Some(10000));
+        assert_eq!(config.extra_lives_between(0, 9999), 0);
+        assert_eq!(config.extra_lives_between(9990, 10010), 1);
+        assert_eq!(config.extra_lives_between(19999, 20000), 1);
+        // several at once, from a big jump in score
+        assert_eq!(config.extra_lives_between(0, 35000), 3);
+        assert_eq!(config.extra_lives_between(25000, 45000), 2);
+    }
+
+    #[test]
+    fn repeating_without_fixed_thresholds() {
+        let config = score_config(vec![], Some(3000));
+        assert_eq!(config.extra_lives_between(0, 2999), 0);
+        assert_eq!(config.extra_lives_between(2999, 6000), 2);
+    }
+
+    #[test]
+    fn zero_interval_is_ignored() {
+        let config = score_config(vec![], Some(0));
+        assert_eq!(config.extra_lives_between(0, 100000), 0);
+    }
+}
@@ -0,0 +1,569 @@
+//! Collider shapes, and the geometry for testing them against each other.
+//!
+//! Every shape breaks down into convex hulls: a convex core (a point, a segment or a polygon)
+//! inflated by a radius.  Two hulls overlap when the distance between their cores is at most the
+//! sum of their radii, which keeps the narrow phase down to a few point and segment tests.
+
+use bevy::{
+    prelude::{Transform, Vec2, Vec3},
+    reflect::{FromReflect, Reflect},
+};
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize, Reflect, FromReflect)]
+#[serde(tag = "type")]
+pub enum ColliderShape {
+    Circle {
+        radius: f32,
+    },
+    /// A convex polygon, with its points listed in order around the outline
+    Polygon {
+        points: Vec<Vec2>,
+    },
+    /// A segment along the local y axis, with rounded ends
+    Capsule {
+        half_length: f32,
+        radius: f32,
+    },
+    /// Several shapes, each placed relative to the collider's origin
+    Compound {
+        parts: Vec<CompoundPart>,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize, Reflect, FromReflect)]
+pub struct CompoundPart {
+    /// not reflected, since reflecting shapes within shapes never terminates
+    #[reflect(ignore)]
+    pub shape: ColliderShape,
+    #[serde(default)]
+    pub offset: Vec2,
+    /// rotation in radians
+    #[serde(default)]
+    pub rotation: f32,
+}
+
+impl Default for ColliderShape {
+    fn default() -> Self {
+        Self::Circle { radius: 0. }
+    }
+}
+
+impl ColliderShape {
+    /// The shape scaled up uniformly, e.g. to match a scaled sprite.
+    pub fn scaled(&self, scale: f32) -> Self {
+        match self {
+            Self::Circle { radius } => Self::Circle {
+                radius: radius * scale,
+            },
+            Self::Polygon { points } => Self::Polygon {
+                points: points.iter().map(|p| *p * scale).collect(),
+            },
+            Self::Capsule {
+                half_length,
+                radius,
+            } => Self::Capsule {
+                half_length: half_length * scale,
+                radius: radius * scale,
+            },
+            Self::Compound { parts } => Self::Compound {
+                parts: parts
+                    .iter()
+                    .map(|part| CompoundPart {
+                        shape: part.shape.scaled(scale),
+                        offset: part.offset * scale,
+                        rotation: part.rotation,
+                    })
+                    .collect(),
+            },
+        }
+    }
+
+    /// Radius of a circle around the origin that contains the whole shape
+    pub fn bounding_radius(&self) -> f32 {
+        self.hulls(Vec2::ZERO, Vec2::X)
+            .iter()
+            .map(|hull| hull.points.iter().map(|p| p.length()).fold(0., f32::max) + hull.radius)
+            .fold(0., f32::max)
+    }
+
+    /// The convex hulls that make up the shape, placed at `position` and rotated by the unit
+    /// vector `rotation`.
+    pub fn hulls(&self, position: Vec2, rotation: Vec2) -> Vec<Hull> {
+        let place = |p: Vec2| position + rotation.rotate(p);
+        match self {
+            Self::Circle { radius } => vec![Hull {
+                points: vec![position],
+                radius: *radius,
+            }],
+            Self::Polygon { points } => vec![Hull {
+                points: points.iter().map(|p| place(*p)).collect(),
+                radius: 0.,
+            }],
+            Self::Capsule {
+                half_length,
+                radius,
+            } => vec![Hull {
+                points: vec![
+                    place(Vec2::new(0., -half_length)),
+                    place(Vec2::new(0., *half_length)),
+                ],
+                radius: *radius,
+            }],
+            Self::Compound { parts } => parts
+                .iter()
+                .flat_map(|part| {
+                    part.shape.hulls(
+                        place(part.offset),
+                        rotation.rotate(Vec2::from_angle(part.rotation)),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The unit vector for the rotation of a transform around the z axis
+pub fn rotation_of(xform: &Transform) -> Vec2 {
+    let right = (xform.rotation * Vec3::X).truncate();
+    right.try_normalize().unwrap_or(Vec2::X)
+}
+
+/// A convex core inflated by a radius.  Circles have a single point as their core, capsules a
+/// segment, and polygons their outline.
+#[derive(Debug, Clone)]
+pub struct Hull {
+    pub points: Vec<Vec2>,
+    pub radius: f32,
+}
+
+impl Hull {
+    /// The area covered by this hull as it moves backwards by `motion`, i.e. from where it was
+    /// `motion` ago to where it is now.  Used for testing fast moving colliders, which could
+    /// otherwise pass through others between ticks.
+    pub fn swept(&self, motion: Vec2) -> Hull {
+        let points: Vec<Vec2> = self.points.iter().flat_map(|p| [*p - motion, *p]).collect();
+        Hull {
+            points: convex_hull(points),
+            radius: self.radius,
+        }
+    }
+
+    pub fn intersects(&self, other: &Hull) -> bool {
+        core_distance(&self.points, &other.points) <= self.radius + other.radius
+    }
+
+    /// How this hull touches another, if they overlap.  The normal points from this hull
+    /// towards the other.
+    pub fn contact(&self, other: &Hull) -> Option<Contact> {
+        if self.points.is_empty() || other.points.is_empty() {
+            return None;
+        }
+        let radii = self.radius + other.radius;
+        match closest_points(&self.points, &other.points) {
+            Some((a, b)) => {
+                let distance = a.distance(b);
+                if distance > radii {
+                    return None;
+                }
+                let normal = (b - a).try_normalize().unwrap_or(Vec2::X);
+                Some(Contact {
+                    // halfway between the two surfaces
+                    point: (a + normal * self.radius + b - normal * other.radius) / 2.,
+                    normal,
+                    depth: radii - distance,
+                })
+            }
+            // the cores themselves overlap
+            None => {
+                let (normal, depth) = least_penetration(&self.points, &other.points);
+                Some(Contact {
+                    point: (support(&self.points, normal) + support(&other.points, -normal)) / 2.,
+                    normal,
+                    depth: depth + radii,
+                })
+            }
+        }
+    }
+
+    pub fn contains_point(&self, point: Vec2) -> bool {
+        core_distance(&self.points, &[point]) <= self.radius
+    }
+}
+
+/// Where two hulls touch, and how far they need to move apart along the unit `normal` to stop
+/// overlapping.
+#[derive(Debug, Clone, Copy)]
+pub struct Contact {
+    pub point: Vec2,
+    pub normal: Vec2,
+    pub depth: f32,
+}
+
+/// The convex hull of a set of points, in counter-clockwise order.  Fewer than three distinct
+/// points are returned as they are, as a point or a segment.
+fn convex_hull(mut points: Vec<Vec2>) -> Vec<Vec2> {
+    points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+    // Andrew's monotone chain
+    let turns_left = |hull: &[Vec2], p: Vec2| {
+        let n = hull.len();
+        (hull[n - 1] - hull[n - 2]).perp_dot(p - hull[n - 2]) > 0.
+    };
+    let mut lower: Vec<Vec2> = Vec::new();
+    for p in points.iter() {
+        while lower.len() >= 2 && !turns_left(&lower, *p) {
+            lower.pop();
+        }
+        lower.push(*p);
+    }
+    let mut upper: Vec<Vec2> = Vec::new();
+    for p in points.iter().rev() {
+        while upper.len() >= 2 && !turns_left(&upper, *p) {
+            upper.pop();
+        }
+        upper.push(*p);
+    }
+    lower.pop();
+    upper.pop();
+    lower.extend(upper);
+    // all points on a line
+    if lower.len() < 3 {
+        return vec![points[0], points[points.len() - 1]];
+    }
+    lower
+}
+
+/// Distance between two convex cores, or zero if they overlap.
+fn core_distance(a: &[Vec2], b: &[Vec2]) -> f32 {
+    if a.is_empty() || b.is_empty() {
+        return f32::MAX;
+    }
+    closest_points(a, b).map_or(0., |(a, b)| a.distance(b))
+}
+
+/// The closest pair of points on two convex cores, or `None` if they overlap.
+fn closest_points(a: &[Vec2], b: &[Vec2]) -> Option<(Vec2, Vec2)> {
+    if polygon_contains_any(a, b) || polygon_contains_any(b, a) {
+        return None;
+    }
+    let mut closest = None;
+    let mut min_dist_sq = f32::MAX;
+    for (a0, a1) in edges(a) {
+        for (b0, b1) in edges(b) {
+            if segments_intersect(a0, a1, b0, b1) {
+                return None;
+            }
+            let (pa, pb) = closest_points_on_segments(a0, a1, b0, b1);
+            let dist_sq = pa.distance_squared(pb);
+            if dist_sq < min_dist_sq {
+                min_dist_sq = dist_sq;
+                closest = Some((pa, pb));
+            }
+        }
+    }
+    closest
+}
+
+/// The axis along which two overlapping convex cores are the least deep into each other, pointing
+/// from `a` towards `b`, and how deep they are along it.
+fn least_penetration(a: &[Vec2], b: &[Vec2]) -> (Vec2, f32) {
+    let mut least = (Vec2::X, 0.);
+    let mut min_depth = f32::MAX;
+    let axes = edges(a)
+        .chain(edges(b))
+        .filter_map(|(p, q)| (q - p).perp().try_normalize());
+    for axis in axes {
+        let (min_a, max_a) = project(a, axis);
+        let (min_b, max_b) = project(b, axis);
+        // push b whichever way along the axis is shorter
+        let (normal, depth) = if max_a - min_b < max_b - min_a {
+            (axis, max_a - min_b)
+        } else {
+            (-axis, max_b - min_a)
+        };
+        if depth < min_depth {
+            min_depth = depth;
+            least = (normal, depth);
+        }
+    }
+    least
+}
+
+/// The range that points cover along an axis
+fn project(points: &[Vec2], axis: Vec2) -> (f32, f32) {
+    points.iter().fold((f32::MAX, f32::MIN), |(min, max), p| {
+        let d = p.dot(axis);
+        (min.min(d), max.max(d))
+    })
+}
+
+/// The point furthest along a direction
+fn support(points: &[Vec2], direction: Vec2) -> Vec2 {
+    points
+        .iter()
+        .copied()
+        .max_by(|a, b| a.dot(direction).total_cmp(&b.dot(direction)))
+        .unwrap_or(Vec2::ZERO)
+}
+
+/// The edges of a convex core.  A point is a single degenerate edge.
+fn edges(points: &[Vec2]) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+    let n = points.len();
+    let edge_count = match n {
+        0 => 0,
+        1 | 2 => 1,
+        _ => n,
+    };
+    (0..edge_count).map(move |i| (points[i], points[(i + 1) % n]))
+}
+
+/// Whether the polygon `poly` contains any of `points`.  Points and segments contain nothing.
+fn polygon_contains_any(poly: &[Vec2], points: &[Vec2]) -> bool {
+    poly.len() >= 3 && points.iter().any(|p| polygon_contains(poly, *p))
+}
+
+/// Whether a convex polygon contains a point, regardless of its winding order.
+fn polygon_contains(poly: &[Vec2], point: Vec2) -> bool {
+    let mut sign = 0.;
+    for (a, b) in edges(poly) {
+        let cross = (b - a).perp_dot(point - a);
+        if cross == 0. {
+            continue;
+        }
+        if sign == 0. {
+            sign = cross.signum();
+        } else if cross.signum() != sign {
+            return false;
+        }
+    }
+    true
+}
+
+/// The closest pair of points on two segments that don't cross
+fn closest_points_on_segments(p1: Vec2, q1: Vec2, p2: Vec2, q2: Vec2) -> (Vec2, Vec2) {
+    [
+        (p1, closest_point_on_segment(p1, p2, q2)),
+        (q1, closest_point_on_segment(q1, p2, q2)),
+        (closest_point_on_segment(p2, p1, q1), p2),
+        (closest_point_on_segment(q2, p1, q1), q2),
+    ]
+    .into_iter()
+    .min_by(|(a1, b1), (a2, b2)| {
+        a1.distance_squared(*b1)
+            .total_cmp(&a2.distance_squared(*b2))
+    })
+    .unwrap()
+}
+
+fn closest_point_on_segment(point: Vec2, a: Vec2, b: Vec2) -> Vec2 {
+    let ab = b - a;
+    let len_sq = ab.length_squared();
+    if len_sq == 0. {
+        return a;
+    }
+    let t = ((point - a).dot(ab) / len_sq).clamp(0., 1.);
+    a + ab * t
+}
+
+/// Whether two segments cross.  Touching and collinear overlapping segments count as crossing.
+fn segments_intersect(p1: Vec2, q1: Vec2, p2: Vec2, q2: Vec2) -> bool {
+    let d1 = (q2 - p2).perp_dot(p1 - p2);
+    let d2 = (q2 - p2).perp_dot(q1 - p2);
+    let d3 = (q1 - p1).perp_dot(p2 - p1);
+    let d4 = (q1 - p1).perp_dot(q2 - p1);
+    if ((d1 > 0. && d2 < 0.) || (d1 < 0. && d2 > 0.))
+        && ((d3 > 0. && d4 < 0.) || (d3 < 0. && d4 > 0.))
+    {
+        return true;
+    }
+    (d1 == 0. && on_segment(p1, p2, q2))
+        || (d2 == 0. && on_segment(q1, p2, q2))
+        || (d3 == 0. && on_segment(p2, p1, q1))
+        || (d4 == 0. && on_segment(q2, p1, q1))
+}
+
+/// Whether a point that is collinear with a segment lies on it
+fn on_segment(point: Vec2, a: Vec2, b: Vec2) -> bool {
+    point.cmpge(a.min(b)).all() && point.cmple(a.max(b)).all()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_4};
+
+    use super::*;
+
+    fn square(half: f32, position: Vec2, rotation: Vec2) -> Hull {
+        let points = vec![
+            Vec2::new(-half, -half),
+            Vec2::new(half, -half),
+            Vec2::new(half, half),
+            Vec2::new(-half, half),
+        ];
+        ColliderShape::Polygon { points }
+            .hulls(position, rotation)
+            .remove(0)
+    }
+
+    fn circle(radius: f32, position: Vec2) -> Hull {
+        ColliderShape::Circle { radius }
+            .hulls(position, Vec2::X)
+            .remove(0)
+    }
+
+    fn capsule(half_length: f32, radius: f32, rotation: Vec2) -> Hull {
+        ColliderShape::Capsule {
+            half_length,
+            radius,
+        }
+        .hulls(Vec2::ZERO, rotation)
+        .remove(0)
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-3,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    fn assert_vec_close(actual: Vec2, expected: Vec2) {
+        assert!(
+            actual.abs_diff_eq(expected, 1e-3),
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn overlapping_polygons() {
+        let a = square(10., Vec2::ZERO, Vec2::X);
+        let b = square(10., Vec2::new(15., 0.), Vec2::X);
+        assert!(a.intersects(&b));
+        assert!(closest_points(&a.points, &b.points).is_none());
+        let contact = a.contact(&b).unwrap();
+        assert_vec_close(contact.normal, Vec2::X);
+        assert_close(contact.depth, 5.);
+    }
+
+    #[test]
+    fn separated_polygons() {
+        let a = square(10., Vec2::ZERO, Vec2::X);
+        let b = square(10., Vec2::new(30., 0.), Vec2::X);
+        assert!(!a.intersects(&b));
+        assert!(a.contact(&b).is_none());
+        let (pa, pb) = closest_points(&a.points, &b.points).unwrap();
+        assert_close(pa.distance(pb), 10.);
+    }
+
+    #[test]
+    fn polygon_inside_polygon() {
+        let outer = square(10., Vec2::ZERO, Vec2::X);
+        let inner = square(2., Vec2::new(1., 0.), Vec2::X);
+        assert!(outer.intersects(&inner));
+        assert!(inner.intersects(&outer));
+        let contact = outer.contact(&inner).unwrap();
+        // the shortest way out is to the right, through the nearest edge
+        assert_vec_close(contact.normal, Vec2::X);
+        assert_close(contact.depth, 11.);
+    }
+
+    #[test]
+    fn rotated_polygons() {
+        // a square turned into a diamond reaches further along the x axis
+        let a = square(10., Vec2::ZERO, Vec2::X);
+        let position = Vec2::new(23., 0.);
+        assert!(!a.intersects(&square(10., position, Vec2::X)));
+        let diamond = square(10., position, Vec2::from_angle(FRAC_PI_4));
+        assert!(a.intersects(&diamond));
+        let contact = a.contact(&diamond).unwrap();
+        assert_vec_close(contact.normal, Vec2::X);
+        assert_close(contact.depth, 10. * 2f32.sqrt() - 13.);
+    }
+
+    #[test]
+    fn capsule_and_circle() {
+        let a = capsule(20., 5., Vec2::X);
+        // beside the segment
+        let contact = a.contact(&circle(10., Vec2::new(12., 0.))).unwrap();
+        assert_vec_close(contact.normal, Vec2::X);
+        assert_close(contact.depth, 3.);
+        assert_vec_close(contact.point, Vec2::new(3.5, 0.));
+        assert!(!a.intersects(&circle(10., Vec2::new(16., 0.))));
+        // off the rounded end
+        let contact = a.contact(&circle(10., Vec2::new(0., 30.))).unwrap();
+        assert_vec_close(contact.normal, Vec2::Y);
+        assert_close(contact.depth, 5.);
+        assert!(!a.intersects(&circle(10., Vec2::new(0., 36.))));
+    }
+
+    #[test]
+    fn rotated_capsule() {
+        // turned a quarter, the capsule lies along the x axis
+        let a = capsule(20., 5., Vec2::from_angle(FRAC_PI_2));
+        assert!(a.intersects(&circle(10., Vec2::new(30., 0.))));
+        assert!(!a.intersects(&circle(10., Vec2::new(0., 16.))));
+        assert!(a.contains_point(Vec2::new(19., 4.)));
+        assert!(!a.contains_point(Vec2::new(0., 6.)));
+    }
+
+    #[test]
+    fn hull_of_collinear_points() {
+        let points = vec![
+            Vec2::new(2., 2.),
+            Vec2::new(0., 0.),
+            Vec2::new(3., 3.),
+            Vec2::new(1., 1.),
+        ];
+        assert_eq!(
+            convex_hull(points),
+            vec![Vec2::new(0., 0.), Vec2::new(3., 3.)]
+        );
+    }
+
+    #[test]
+    fn hull_of_duplicate_points() {
+        let point = Vec2::new(1., 2.);
+        assert_eq!(convex_hull(vec![point, point, point]), vec![point]);
+
+        let corners = [
+            Vec2::new(-1., -1.),
+            Vec2::new(1., -1.),
+            Vec2::new(1., 1.),
+            Vec2::new(-1., 1.),
+        ];
+        let mut points: Vec<Vec2> = corners.iter().chain(corners.iter()).copied().collect();
+        points.push(Vec2::ZERO);
+        assert_eq!(convex_hull(points), corners.to_vec());
+    }
+
+    #[test]
+    fn swept_hulls() {
+        let motion = Vec2::new(100., 0.);
+        let a = circle(5., Vec2::ZERO);
+        let swept = a.swept(motion);
+        assert_eq!(swept.points, vec![Vec2::new(-100., 0.), Vec2::ZERO]);
+        // something the circle passed over since the last tick
+        let b = circle(5., Vec2::new(-50., 8.));
+        assert!(!a.intersects(&b));
+        assert!(swept.intersects(&b));
+
+        let swept = square(1., Vec2::ZERO, Vec2::X).swept(Vec2::new(10., 0.));
+        assert_eq!(
+            swept.points,
+            vec![
+                Vec2::new(-11., -1.),
+                Vec2::new(1., -1.),
+                Vec2::new(1., 1.),
+                Vec2::new(-11., 1.),
+            ]
+        );
+    }
+}
@@ -1,79 +1,111 @@
 use bevy::{
     math::Vec3Swizzles,
     prelude::{
-        Bundle, Commands, Component, Image, IntoSystemConfig, OnUpdate, Plugin, Query, Res,
-        Transform,
+        Bundle, Color, Component, IntoSystemConfig, OnUpdate, Plugin, Query, Res, Transform,
+        Without,
     },
     reflect::Reflect,
-    sprite::SpriteBundle,
-    time::{Time, Timer},
+    sprite::{Sprite, SpriteBundle},
+    time::Time,
 };
-use bevy_kira_audio::{Audio, AudioControl, AudioSource};
 use serde::Deserialize;
 
 use crate::{
     app::AppState,
-    collision::Collider,
+    collision::{Collider, CollisionLayers},
+    hyperspace::{HyperspaceCooldown, InHyperspace},
     kinematics::{Acceleration, AngularAcceleration, KinematicsBundle},
-    loading::AssetMap,
-    projectile::{ProjectileBundle, ProjectileConfig},
+    particle::ParticleEmitter,
+    powerup::ShieldPowerup,
+    shape::ColliderShape,
+    weapon::WeaponState,
 };
 
+/// Tint for ships with their shield up
+const SHIELD_TINT: Color = Color::rgb(0.5, 0.8, 1.);
+
 pub struct ShipPlugin;
 
+/// A ship, with what its controls steer
+type SteeredShip<'a> = (
+    &'a ShipControls,
+    &'a ShipConfig,
+    &'a Boost,
+    &'a Transform,
+    &'a mut Acceleration,
+    &'a mut AngularAcceleration,
+    &'a mut ParticleEmitter,
+);
+
+/// A ship, with what runs on its energy
+type PoweredShip<'a> = (
+    &'a ShipControls,
+    &'a ShipConfig,
+    &'a mut Energy,
+    &'a mut Shield,
+    &'a mut Boost,
+    &'a mut Sprite,
+    Option<&'a ShieldPowerup>,
+);
+
 impl Plugin for ShipPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
         app.register_type::<ShipControls>();
         app.register_type::<ShipConfig>();
-        app.add_system(Self::system_handle_controls.in_set(OnUpdate(AppState::InGame)));
-        app.add_system(Self::system_shoot_cooldown.in_set(OnUpdate(AppState::InGame)));
+        app.add_system(Self::system_energy.in_set(OnUpdate(AppState::InGame)));
+        app.add_system(
+            Self::system_handle_controls
+                .after(Self::system_energy)
+                .in_set(OnUpdate(AppState::InGame)),
+        );
     }
 }
 
 impl ShipPlugin {
     fn system_handle_controls(
-        mut commands: Commands,
-        mut q: Query<(
-            &ShipControls,
-            &mut ShootCooldown,
-            &ShipConfig,
-            &Transform,
-            &mut Acceleration,
-            &mut AngularAcceleration,
-        )>,
-        projectile_config: Res<ProjectileConfig>,
-        sprites: Res<AssetMap<Image>>,
-        audio: Res<Audio>,
-        audio_sources: Res<AssetMap<AudioSource>>,
+        // ships can't steer from hyperspace
+        mut q: Query<SteeredShip, Without<InHyperspace>>,
     ) {
-        let sound = audio_sources.0.get("laser").unwrap();
         q.iter_mut().for_each(
-            |(controls, mut shoot_cooldown, config, xform, mut acc, mut racc)| {
+            |(controls, config, boost, xform, mut acc, mut racc, mut exhaust)| {
                 // update kinematics
                 let direction = xform.up().xy();
-                acc.0 = direction * controls.thrust * config.thrust_factor;
+                let thrust_factor = if boost.0 {
+                    config.thrust_factor * config.boost_factor
+                } else {
+                    config.thrust_factor
+                };
+                acc.0 = direction * controls.thrust * thrust_factor;
                 racc.0 = controls.turn * config.turn_factor;
-                // handle shooting
-                if controls.shoot && shoot_cooldown.0.finished() {
-                    // spawn projectile
-                    let projectile =
-                        ProjectileBundle::from_config(&projectile_config, xform, &sprites);
-                    commands.spawn(projectile);
-                    shoot_cooldown.0.reset();
-                    // play projectile sound
-                    // TODO: configure paths
-                    audio.play(sound.clone());
-
-                    // TODO: projectile kickback
-                }
+                exhaust.active = controls.thrust > 0.;
             },
         );
     }
 
-    fn system_shoot_cooldown(mut q: Query<&mut ShootCooldown>, time: Res<Time>) {
-        q.iter_mut().for_each(|mut t| {
-            t.0.tick(time.delta());
-        });
+    /// Spend energy on shields and boost, or recharge when neither is in use.
+    fn system_energy(mut q: Query<PoweredShip>, time: Res<Time>) {
+        let dt = time.delta_seconds();
+        for (controls, config, mut energy, mut shield, mut boost, mut sprite, shield_powerup) in
+            q.iter_mut()
+        {
+            // a shield power-up keeps the shield up for free
+            let free_shield = shield_powerup.is_some();
+            shield.0 = free_shield || (controls.shield && energy.0 > 0.);
+            boost.0 = controls.boost && controls.thrust > 0. && energy.0 > 0.;
+            let mut drain = 0.;
+            if shield.0 && !free_shield {
+                drain += config.shield_drain;
+            }
+            if boost.0 {
+                drain += config.boost_drain;
+            }
+            energy.0 = if drain > 0. {
+                (energy.0 - drain * dt).max(0.)
+            } else {
+                (energy.0 + config.energy_recharge * dt).min(config.energy_capacity)
+            };
+            sprite.color = if shield.0 { SHIELD_TINT } else { Color::WHITE };
+        }
     }
 }
 
@@ -84,10 +116,22 @@ pub struct ShipControls {
     /// turn input [-1, 1]
     pub turn: f32,
     pub shoot: bool,
+    pub hyperspace: bool,
+    pub shield: bool,
+    pub boost: bool,
 }
 
+/// Energy left for shields and boost
 #[derive(Debug, Component)]
-pub struct ShootCooldown(pub Timer);
+pub struct Energy(pub f32);
+
+/// Whether the ship's shield is up, and bouncing meteors away
+#[derive(Debug, Default, Component)]
+pub struct Shield(pub bool);
+
+/// Whether the ship is boosting its thrust
+#[derive(Debug, Default, Component)]
+pub struct Boost(pub bool);
 
 #[derive(Reflect, Component, Clone, Debug, Default, Deserialize)]
 pub struct ShipConfig {
@@ -96,8 +140,29 @@ pub struct ShipConfig {
     pub velocity_damping: f32,
     pub rotation_rate_damping: f32,
     pub sprite_id: String,
-    pub collision_radius: f32,
-    pub shoot_cooldown: f32,
+    pub collision_shape: ColliderShape,
+    pub collision_layers: CollisionLayers,
+    pub collision_mask: CollisionLayers,
+    /// id of the ship's weapon, from the weapons config
+    pub weapon: String,
+    /// seconds between hyperspace jumps
+    pub hyperspace_cooldown: f32,
+    /// seconds that the ship is gone while jumping
+    pub hyperspace_duration: f32,
+    /// chance of blowing up on re-entry, from 0 to 1
+    pub hyperspace_failure_chance: f32,
+    /// maximum energy
+    pub energy_capacity: f32,
+    /// energy gained per second while the shield and boost are off
+    pub energy_recharge: f32,
+    /// energy spent per second while the shield is up
+    pub shield_drain: f32,
+    /// energy spent per second while boosting
+    pub boost_drain: f32,
+    /// thrust multiplier while boosting
+    pub boost_factor: f32,
+    /// distance behind the ship's center that the exhaust comes out of
+    pub exhaust_offset: f32,
 }
 
 #[derive(Bundle)]
@@ -105,7 +170,12 @@ pub struct ShipBundle {
     pub controls: ShipControls,
     pub config: ShipConfig,
     pub collider: Collider,
-    pub shoot_cooldown: ShootCooldown,
+    pub weapon: WeaponState,
+    pub hyperspace_cooldown: HyperspaceCooldown,
+    pub energy: Energy,
+    pub shield: Shield,
+    pub boost: Boost,
+    pub exhaust: ParticleEmitter,
 
     #[bundle]
     pub sprite: SpriteBundle,
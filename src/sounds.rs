@@ -1,19 +1,22 @@
-use std::sync::{Arc, Mutex};
-
 use bevy::{
     prelude::{
-        Commands, Component, Handle, Image, IntoSystemAppConfig, IntoSystemConfig, MouseButton,
-        NonSend, OnEnter, OnExit, OnUpdate, Plugin, Query, Res, ResMut, Resource, Transform, Vec3,
-        With, World,
+        warn, Assets, Commands, Component, EventReader, Handle, Image, IntoSystemAppConfig,
+        IntoSystemConfig, MouseButton, OnEnter, OnExit, OnUpdate, Plugin, Query, Res, ResMut,
+        Resource, Transform, Vec3, With,
     },
     sprite::SpriteBundle,
     utils::default,
 };
-use bevy_kira_audio::{Audio, AudioControl, AudioInstance, AudioPlugin, AudioSource};
-use web_sys::AudioContext;
+use bevy_kira_audio::{Audio, AudioControl, AudioInstance, AudioSource, AudioTween};
 
 use crate::{
-    app::AppState, collision::Collider, input::ClickListener, loading::AssetMap,
+    app::{ending_game, starting_new_game, AppState},
+    collision::Collider,
+    input::ClickListener,
+    loading::AssetMap,
+    player::PlayerMarker,
+    shape::ColliderShape,
+    ship::ShipControls,
     viewport::ViewportBounds,
 };
 
@@ -21,14 +24,43 @@ pub struct SoundsPlugin;
 
 impl Plugin for SoundsPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
+        app.add_event::<SoundEvent>();
         app.add_system(Self::system_spawn_sound_button.in_schedule(OnEnter(AppState::Splash)));
-        app.add_system(Self::system_start_music.in_schedule(OnEnter(AppState::InGame)));
+        app.add_system(
+            Self::system_start_music
+                .run_if(starting_new_game)
+                .in_schedule(OnEnter(AppState::InGame)),
+        );
+        app.add_system(
+            Self::system_start_thruster
+                .run_if(starting_new_game)
+                .in_schedule(OnEnter(AppState::InGame)),
+        );
+        app.add_system(
+            Self::system_stop_game_audio
+                .run_if(ending_game)
+                .in_schedule(OnExit(AppState::InGame)),
+        );
+        app.add_system(
+            Self::system_stop_game_audio
+                .after(Self::system_resume_audio)
+                .run_if(ending_game)
+                .in_schedule(OnExit(AppState::Paused)),
+        );
+        app.add_system(Self::system_pause_audio.in_schedule(OnEnter(AppState::Paused)));
+        app.add_system(Self::system_resume_audio.in_schedule(OnExit(AppState::Paused)));
+        app.add_system(Self::system_play_sound_events);
 
         app.add_system(Self::system_handle_sound_button_clicked.in_set(OnUpdate(AppState::Splash)));
         app.add_system(Self::system_update_sound_button.in_set(OnUpdate(AppState::Splash)));
+        app.add_system(
+            Self::system_handle_sound_button_clicked.in_set(OnUpdate(AppState::MainMenu)),
+        );
+        app.add_system(Self::system_update_sound_button.in_set(OnUpdate(AppState::MainMenu)));
         app.add_system(Self::system_handle_sound_button_clicked.in_set(OnUpdate(AppState::InGame)));
         app.add_system(Self::system_update_sound_button.in_set(OnUpdate(AppState::InGame)));
         app.add_system(Self::system_update_sound_volume.in_set(OnUpdate(AppState::InGame)));
+        app.add_system(Self::system_thruster_sound.in_set(OnUpdate(AppState::InGame)));
     }
 }
 
@@ -43,6 +75,82 @@ impl SoundsPlugin {
         commands.insert_resource(MusicAudio(handle));
     }
 
+    fn system_start_thruster(
+        mut commands: Commands,
+        audio: Res<Audio>,
+        loaded_audio: Res<AssetMap<AudioSource>>,
+        mut audio_instances: ResMut<Assets<AudioInstance>>,
+    ) {
+        let sound = loaded_audio.0.get("thruster").unwrap();
+        let handle = audio.play(sound.clone()).looped().handle();
+        if let Some(instance) = audio_instances.get_mut(&handle) {
+            instance.set_volume(0., AudioTween::default());
+        }
+        commands.insert_resource(ThrusterSound(handle));
+    }
+
+    fn system_pause_audio(audio: Res<Audio>) {
+        audio.pause();
+    }
+
+    /// Resume the paused sounds when leaving the pause menu.  When quitting, the looping ones are
+    /// stopped right after.
+    fn system_resume_audio(audio: Res<Audio>) {
+        audio.resume();
+    }
+
+    /// Stop the looping sounds of a game, so that the next game can start them afresh.
+    fn system_stop_game_audio(
+        mut commands: Commands,
+        music: Option<Res<MusicAudio>>,
+        thruster_sound: Option<Res<ThrusterSound>>,
+        mut audio_instances: ResMut<Assets<AudioInstance>>,
+    ) {
+        let handles = [
+            music.map(|m| m.0.clone()),
+            thruster_sound.map(|t| t.0.clone()),
+        ];
+        for handle in handles.iter().flatten() {
+            if let Some(instance) = audio_instances.get_mut(handle) {
+                instance.stop(AudioTween::default());
+            }
+        }
+        commands.remove_resource::<MusicAudio>();
+        commands.remove_resource::<ThrusterSound>();
+    }
+
+    fn system_thruster_sound(
+        thruster_sound: Res<ThrusterSound>,
+        mut audio_instances: ResMut<Assets<AudioInstance>>,
+        q: Query<&ShipControls, With<PlayerMarker>>,
+    ) {
+        // the thruster goes quiet while the player is dead
+        let thrust = q.get_single().map_or(0., |controls| controls.thrust);
+        if let Some(instance) = audio_instances.get_mut(&thruster_sound.0) {
+            instance.set_volume(thrust as f64, AudioTween::default());
+        }
+    }
+
+    fn system_play_sound_events(
+        mut evr_sounds: EventReader<SoundEvent>,
+        audio: Res<Audio>,
+        loaded_audio: Option<Res<AssetMap<AudioSource>>>,
+    ) {
+        // sounds can't play before the audio assets are loaded
+        let Some(loaded_audio) = loaded_audio else {
+            evr_sounds.clear();
+            return;
+        };
+        for ev in evr_sounds.iter() {
+            match loaded_audio.0.get(&ev.sound_id) {
+                Some(sound) => {
+                    audio.play(sound.clone());
+                }
+                None => warn!("Could not find sound: {}", ev.sound_id),
+            }
+        }
+    }
+
     fn system_spawn_sound_button(
         mut commands: Commands,
         loaded_images: Res<AssetMap<Image>>,
@@ -63,7 +171,10 @@ impl SoundsPlugin {
             },
             SoundButton,
             ClickListener::default(),
-            Collider { radius: 32. },
+            Collider {
+                shape: ColliderShape::Circle { radius: 32. },
+                ..default()
+            },
         );
         commands.spawn(sound_button);
         commands.insert_resource(sound_on);
@@ -110,11 +221,30 @@ impl SoundsPlugin {
     }
 }
 
+/// Request to play a one-shot sound effect.  Gameplay code sends these instead of talking to the
+/// audio backend, so that it can also run without one.
+#[derive(Debug, Clone)]
+pub struct SoundEvent {
+    /// key of the sound in the audio assets config
+    pub sound_id: String,
+}
+
+impl SoundEvent {
+    pub fn new(sound_id: &str) -> Self {
+        Self {
+            sound_id: String::from(sound_id),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Resource)]
 struct MusicAudio(Handle<AudioInstance>);
 
 #[derive(Debug, Clone, Resource)]
-struct SoundOn(bool);
+struct ThrusterSound(Handle<AudioInstance>);
+
+#[derive(Debug, Clone, Resource)]
+pub struct SoundOn(pub bool);
 
 #[derive(Debug, Clone, Component)]
 struct SoundButton;
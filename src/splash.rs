@@ -7,7 +7,10 @@ use bevy::{
     text::{Text, Text2dBundle, TextStyle},
 };
 
-use crate::{app::AppState, collision::Collider, input::ClickListener, viewport::ViewportBounds};
+use crate::{
+    app::AppState, collision::Collider, input::ClickListener, shape::ColliderShape,
+    viewport::ViewportBounds,
+};
 
 #[derive(Debug)]
 pub struct SplashPlugin;
@@ -49,7 +52,10 @@ impl SplashPlugin {
         let radius = viewport_bounds.0.half_size().max_element();
         commands.spawn((
             ClickListener::default(),
-            Collider { radius },
+            Collider {
+                shape: ColliderShape::Circle { radius },
+                ..default()
+            },
             Transform {
                 translation: Vec3::new(0., 0., -1.),
                 ..default()
@@ -68,7 +74,7 @@ impl SplashPlugin {
             .flat_map(|mut listener| listener.0.drain().collect::<Vec<_>>())
             .collect();
         if !events.is_empty() {
-            next_state.set(AppState::InGame);
+            next_state.set(AppState::MainMenu);
         }
     }
 
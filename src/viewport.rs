@@ -3,9 +3,9 @@
 use bevy::{
     math::Vec3Swizzles,
     prelude::{
-        info, Camera2dBundle, ClearColor, Color, Commands, Component, DetectChanges,
-        IntoSystemAppConfig, IntoSystemConfig, OnUpdate, OrthographicProjection, Plugin, Query,
-        Rect, Res, ResMut, Resource, Transform, Vec2, With,
+        in_state, info, Camera2dBundle, ClearColor, Color, Commands, Component, CoreSchedule,
+        DetectChanges, IntoSystemAppConfig, IntoSystemConfig, OrthographicProjection, Plugin,
+        Query, Rect, Res, ResMut, Resource, Transform, Vec2, Vec3, With,
     },
     reflect::Reflect,
     window::{Window, WindowResolution},
@@ -13,16 +13,34 @@ use bevy::{
 use bevy_mod_gizmos::GizmoInteractionCamera;
 use serde::Deserialize;
 
-use crate::app::AppState;
+use crate::{
+    app::AppState,
+    kinematics::{KinematicsSet, PreviousTransform},
+};
 
 pub struct ViewportPlugin;
 
 impl Plugin for ViewportPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
-        app.register_type::<ViewportBounds>();
+        app.add_plugin(ViewportBoundedPlugin);
         app.add_system(PrimaryCameraMarker::system_spawn.on_startup());
         app.add_system(ViewportConfig::system_handle_changed);
-        app.add_system(system_update_viewport_bounded.in_set(OnUpdate(AppState::InGame)));
+    }
+}
+
+/// Wraps viewport bounded entities around the viewport bounds.  Unlike the rest of the
+/// `ViewportPlugin`, this doesn't need a window.
+pub struct ViewportBoundedPlugin;
+
+impl Plugin for ViewportBoundedPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.register_type::<ViewportBounds>();
+        app.add_system(
+            system_update_viewport_bounded
+                .after(KinematicsSet)
+                .run_if(in_state(AppState::InGame))
+                .in_schedule(CoreSchedule::FixedUpdate),
+        );
     }
 }
 
@@ -31,7 +49,7 @@ pub struct ViewportConfig {
     title: String,
     fit_canvas_to_parent: bool,
     prevent_default_event_handling: bool,
-    resolution: [f32; 2],
+    pub resolution: [f32; 2],
     background_color: String,
     camera_scale: f32,
     bounding_margin: f32,
@@ -40,11 +58,31 @@ pub struct ViewportConfig {
 #[derive(Resource, Clone, Reflect, Debug)]
 pub struct ViewportBounds(pub Rect);
 
+impl ViewportBounds {
+    /// The shortest displacement equivalent to `delta` on the torus that viewport bounded
+    /// entities live on, i.e. the minimum image of `delta`.
+    pub fn wrap_delta(&self, delta: Vec2) -> Vec2 {
+        let size = self.0.size();
+        delta - size * (delta / size).round()
+    }
+}
+
 /// Component indicating that a component is bounded to the viewport, and will repeat itself.
 #[derive(Component, Clone, Debug, Reflect, Default)]
 pub struct ViewportBounded;
 
 impl ViewportConfig {
+    /// The area that the camera shows, without the bounding margin.
+    pub fn visible_rect(&self) -> Rect {
+        Rect::from_center_size(Vec2::ZERO, Vec2::from(self.resolution) * self.camera_scale)
+    }
+
+    /// The bounds that viewport bounded entities wrap around in.
+    pub fn bounds(&self) -> ViewportBounds {
+        let bounds_size = Vec2::from(self.resolution) * self.camera_scale + self.bounding_margin;
+        ViewportBounds(Rect::from_center_size(Vec2::ZERO, bounds_size))
+    }
+
     /// handle changed viewport config by updating properties
     fn system_handle_changed(
         mut commands: Commands,
@@ -81,9 +119,7 @@ impl ViewportConfig {
             .iter_mut()
             .for_each(|mut p| p.scale = viewport_config.camera_scale);
 
-        let bounds_size = Vec2::from(viewport_config.resolution) * viewport_config.camera_scale
-            + viewport_config.bounding_margin;
-        let viewport_bounds = ViewportBounds(Rect::from_center_size(Vec2::ZERO, bounds_size));
+        let viewport_bounds = viewport_config.bounds();
         info!("Viewport bounds: {:?}", viewport_bounds);
         commands.insert_resource(viewport_bounds);
     }
@@ -102,12 +138,12 @@ impl PrimaryCameraMarker {
     }
 }
 
-fn system_update_viewport_bounded(
+pub fn system_update_viewport_bounded(
     bounds: Res<ViewportBounds>,
-    mut q: Query<&mut Transform, With<ViewportBounded>>,
+    mut q: Query<(&mut Transform, Option<&mut PreviousTransform>), With<ViewportBounded>>,
 ) {
     let bounds_size = bounds.0.size();
-    for mut xform in q.iter_mut() {
+    for (mut xform, prev) in q.iter_mut() {
         let mut bounds_pos = xform.translation.xy() - bounds.0.min;
 
         while bounds_pos.x < 0. {
@@ -119,7 +155,18 @@ fn system_update_viewport_bounded(
         }
         bounds_pos.y %= bounds_size.y;
         let pos = bounds_pos + bounds.0.min;
+        let offset = pos - xform.translation.xy();
+        if offset == Vec2::ZERO {
+            continue;
+        }
         xform.translation.x = pos.x;
         xform.translation.y = pos.y;
+        // move the previous transform along, so that interpolation doesn't streak across the
+        // screen
+        if let Some(mut prev) = prev {
+            if let Some(prev_xform) = prev.0.as_mut() {
+                prev_xform.translation += Vec3::from((offset, 0.));
+            }
+        }
     }
 }
@@ -0,0 +1,122 @@
+//! Waves of meteors, which get harder as the game goes on.
+//!
+//! A new wave starts a short intermission after the last meteor of the previous one is destroyed.
+//! How many meteors a wave has, how fast they move and which variants show up all follow curves
+//! from the config.  The meteor module does the actual spawning when a wave starts.
+
+use std::collections::BTreeMap;
+
+use bevy::{
+    prelude::{
+        info, EventWriter, IntoSystemAppConfig, IntoSystemConfig, OnEnter, OnUpdate, Plugin, Query,
+        Res, ResMut, Resource, With,
+    },
+    time::{Time, Timer, TimerMode},
+};
+use serde::Deserialize;
+
+use crate::{
+    app::{starting_new_game, AppState, LaunchOptions},
+    meteor::MeteorBehavior,
+};
+
+pub struct WavePlugin;
+
+impl Plugin for WavePlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.init_resource::<Wave>();
+        app.add_event::<WaveStarted>();
+        app.add_event::<WaveCleared>();
+        app.add_system(
+            Wave::system_reset
+                .run_if(starting_new_game)
+                .in_schedule(OnEnter(AppState::InGame)),
+        );
+        app.add_system(Wave::system_update.in_set(OnUpdate(AppState::InGame)));
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Resource)]
+pub struct WaveConfig {
+    /// seconds between clearing a wave and starting the next one
+    pub intermission: f32,
+    /// how close to the player meteors can spawn
+    pub spawn_clearance: f32,
+    /// number of large meteors, rounded to the nearest whole meteor
+    pub meteor_count: Curve,
+    /// multiplies the configured meteor speeds, including for fragments
+    pub speed_multiplier: Curve,
+    /// relative chance of each meteor variant showing up, by variant name.  Variants without a
+    /// curve don't show up, unless no variant has one.
+    #[serde(default)]
+    pub variant_weights: BTreeMap<String, Curve>,
+}
+
+/// A value that grows by a fixed amount with every wave, up to an optional maximum.
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct Curve {
+    /// value for the first wave
+    pub base: f32,
+    #[serde(default)]
+    pub per_wave: f32,
+    pub max: Option<f32>,
+}
+
+impl Curve {
+    pub fn at(&self, wave: u32) -> f32 {
+        let value = self.base + self.per_wave * wave.saturating_sub(1) as f32;
+        self.max.map_or(value, |max| value.min(max))
+    }
+}
+
+/// The current wave, counting from 1.  During an intermission this is the wave that was just
+/// cleared.
+#[derive(Debug, Default, Resource)]
+pub struct Wave {
+    pub number: u32,
+    intermission: Option<Timer>,
+}
+
+/// Sent when a wave starts, with its number.
+#[derive(Debug, Clone, Copy)]
+pub struct WaveStarted(pub u32);
+
+/// Sent when the last meteor of a wave is destroyed, with the wave's number.
+#[derive(Debug, Clone, Copy)]
+pub struct WaveCleared(pub u32);
+
+impl Wave {
+    /// Start the first wave (or the one picked at launch) right away.
+    fn system_reset(mut wave: ResMut<Wave>, options: Option<Res<LaunchOptions>>) {
+        let start_wave = options.map_or(1, |options| options.start_wave.max(1));
+        wave.number = start_wave - 1;
+        wave.intermission = Some(Timer::from_seconds(0., TimerMode::Once));
+    }
+
+    /// Start the next wave when the intermission is over, or start an intermission when the
+    /// current wave is cleared.  Meteors spawned for a new wave only show up in the next frame, so
+    /// the meteor spawning runs after this.
+    pub fn system_update(
+        mut wave: ResMut<Wave>,
+        time: Res<Time>,
+        config: Res<WaveConfig>,
+        q_meteors: Query<(), With<MeteorBehavior>>,
+        mut evw_started: EventWriter<WaveStarted>,
+        mut evw_cleared: EventWriter<WaveCleared>,
+    ) {
+        if let Some(intermission) = wave.intermission.as_mut() {
+            if intermission.tick(time.delta()).finished() {
+                wave.intermission = None;
+                wave.number += 1;
+                info!("Wave {} started", wave.number);
+                evw_started.send(WaveStarted(wave.number));
+            }
+            return;
+        }
+        if q_meteors.is_empty() {
+            info!("Wave {} cleared", wave.number);
+            evw_cleared.send(WaveCleared(wave.number));
+            wave.intermission = Some(Timer::from_seconds(config.intermission, TimerMode::Once));
+        }
+    }
+}
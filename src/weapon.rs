@@ -0,0 +1,289 @@
+//! Ship weapons, defined in the config under `[weapons.*]`.
+//!
+//! A weapon has a fire pattern, and can be limited by a cooldown, ammo and heat.  Ships refer to
+//! a weapon by id, and power-ups can swap it for another one for a while.
+
+use std::time::Duration;
+
+use bevy::{
+    math::Vec3Swizzles,
+    prelude::{
+        Commands, Component, EventWriter, Image, IntoSystemAppConfig, IntoSystemConfig, OnEnter,
+        OnUpdate, Plugin, Quat, Query, Res, Resource, Transform, Vec2, Without,
+    },
+    time::{Time, Timer, TimerMode},
+    utils::HashMap,
+};
+use serde::Deserialize;
+
+use crate::{
+    app::AppState,
+    hyperspace::InHyperspace,
+    kinematics::Velocity,
+    loading::AssetMap,
+    powerup::{RapidFire, SpreadShot},
+    projectile::{ProjectileBundle, ProjectileConfig},
+    ship::{ShipConfig, ShipControls},
+    sounds::SoundEvent,
+};
+
+#[derive(Debug)]
+pub struct WeaponPlugin;
+
+impl Plugin for WeaponPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.add_system(WeaponState::system_fire.in_set(OnUpdate(AppState::InGame)));
+        app.add_system(WeaponState::system_cancel_charge.in_schedule(OnEnter(AppState::Paused)));
+    }
+}
+
+/// A ship that can fire, along with the power-ups that change its weapon
+type ArmedShip<'a> = (
+    &'a ShipControls,
+    &'a ShipConfig,
+    &'a Transform,
+    &'a mut Velocity,
+    &'a mut WeaponState,
+    Option<&'a RapidFire>,
+    Option<&'a SpreadShot>,
+);
+
+/// All weapons, by id
+#[derive(Debug, Clone, Default, Deserialize, Resource)]
+pub struct WeaponsConfig(HashMap<String, WeaponConfig>);
+
+impl WeaponsConfig {
+    pub fn contains(&self, weapon_id: &str) -> bool {
+        self.0.contains_key(weapon_id)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WeaponConfig {
+    pattern: FirePattern,
+    /// seconds between shots
+    cooldown: f32,
+    #[serde(default)]
+    ammo: Option<AmmoConfig>,
+    #[serde(default)]
+    heat: Option<HeatConfig>,
+    /// speed the ship loses with every shot, pushing it backwards
+    #[serde(default)]
+    recoil: f32,
+    projectile: ProjectileConfig,
+}
+
+/// How projectiles come out of a weapon when it is fired
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type")]
+pub enum FirePattern {
+    /// one projectile straight ahead
+    Single,
+    /// several projectiles at once, fanned out with `angle` radians between neighbours
+    Spread { count: u32, angle: f32 },
+    /// several projectiles in a row, `interval` seconds apart
+    Burst { count: u32, interval: f32 },
+    /// one projectile when the trigger is released, growing up to `max_scale` times its size
+    /// while it is held for up to `charge_time` seconds
+    Charge { charge_time: f32, max_scale: f32 },
+}
+
+/// A magazine that reloads once it is empty.  Every pull of the trigger takes one round, however
+/// many projectiles it fires, so a whole spread or burst costs one.
+#[derive(Debug, Clone, Deserialize)]
+struct AmmoConfig {
+    capacity: u32,
+    /// seconds to refill an empty magazine
+    reload_time: f32,
+}
+
+/// Heat builds up with every shot.  At 1 the weapon overheats, and can't fire again until it has
+/// cooled down completely.
+#[derive(Debug, Clone, Deserialize)]
+struct HeatConfig {
+    per_shot: f32,
+    /// heat lost per second
+    cooling: f32,
+}
+
+/// The state of a ship's current weapon.  It starts over whenever the ship switches weapons.
+#[derive(Debug, Default, Component)]
+pub struct WeaponState {
+    weapon_id: String,
+    cooldown: Timer,
+    ammo: Option<u32>,
+    reload: Option<Timer>,
+    heat: f32,
+    overheated: bool,
+    /// shots left in the current burst, and the time until the next one
+    burst: Option<(u32, Timer)>,
+    /// seconds that the trigger has been held for a charged shot
+    charge: Option<f32>,
+}
+
+impl WeaponState {
+    fn new(weapon_id: &str, weapon: &WeaponConfig) -> Self {
+        // ready to fire right away
+        let mut cooldown = Timer::from_seconds(weapon.cooldown, TimerMode::Once);
+        cooldown.tick(cooldown.duration());
+        Self {
+            weapon_id: String::from(weapon_id),
+            cooldown,
+            ammo: weapon.ammo.as_ref().map(|ammo| ammo.capacity),
+            ..Default::default()
+        }
+    }
+
+    fn tick(&mut self, weapon: &WeaponConfig, delta: Duration) {
+        self.cooldown.tick(delta);
+        if let (Some(reload), Some(ammo)) = (self.reload.as_mut(), weapon.ammo.as_ref()) {
+            if reload.tick(delta).finished() {
+                self.ammo = Some(ammo.capacity);
+                self.reload = None;
+            }
+        }
+        if let Some(heat) = weapon.heat.as_ref() {
+            self.heat = (self.heat - heat.cooling * delta.as_secs_f32()).max(0.);
+            if self.heat == 0. {
+                self.overheated = false;
+            }
+        }
+    }
+
+    fn can_fire(&self) -> bool {
+        self.cooldown.finished() && self.ammo != Some(0) && !self.overheated && self.burst.is_none()
+    }
+
+    /// Spend ammo and build up heat for a shot, and start the cooldown
+    fn consume(&mut self, weapon: &WeaponConfig, cooldown_factor: f32) {
+        self.cooldown
+            .set_duration(Duration::from_secs_f32(weapon.cooldown * cooldown_factor));
+        self.cooldown.reset();
+        if let (Some(ammo), Some(ammo_config)) = (self.ammo.as_mut(), weapon.ammo.as_ref()) {
+            *ammo = ammo.saturating_sub(1);
+            if *ammo == 0 {
+                self.reload = Some(Timer::from_seconds(
+                    ammo_config.reload_time,
+                    TimerMode::Once,
+                ));
+            }
+        }
+        if let Some(heat) = weapon.heat.as_ref() {
+            self.heat += heat.per_shot;
+            if self.heat >= 1. {
+                self.overheated = true;
+            }
+        }
+    }
+
+    /// Drop charged shots when pausing, since the trigger release is missed during the pause, and
+    /// they would go off as soon as the game resumes.
+    fn system_cancel_charge(mut q: Query<&mut WeaponState>) {
+        for mut state in q.iter_mut() {
+            state.charge = None;
+        }
+    }
+
+    fn system_fire(
+        mut commands: Commands,
+        // ships can't shoot from hyperspace
+        mut q: Query<ArmedShip, Without<InHyperspace>>,
+        weapons: Res<WeaponsConfig>,
+        sprites: Res<AssetMap<Image>>,
+        time: Res<Time>,
+        mut evw_sounds: EventWriter<SoundEvent>,
+    ) {
+        for (controls, config, xform, mut vel, mut state, rapid_fire, spread_shot) in q.iter_mut() {
+            // power-ups can swap the ship's weapon
+            let weapon_id = spread_shot.map_or(&config.weapon, |s| &s.weapon);
+            // unknown weapons are reported when the config is loaded
+            let Some(weapon) = weapons.0.get(weapon_id) else {
+                continue;
+            };
+            if state.weapon_id != *weapon_id {
+                *state = WeaponState::new(weapon_id, weapon);
+            }
+            state.tick(weapon, time.delta());
+            let cooldown_factor = rapid_fire.map_or(1., |r| r.cooldown_factor);
+            let mut fire = |scale: f32| {
+                spawn_volley(&mut commands, weapon, xform, vel.0, &sprites, scale);
+                // bigger shots kick harder
+                vel.0 -= xform.up().xy() * weapon.recoil * scale;
+                // TODO: configure paths
+                evw_sounds.send(SoundEvent::new("laser"));
+            };
+
+            // the rest of a burst follows the first shot
+            if let Some((shots_left, timer)) = state.burst.as_mut() {
+                if timer.tick(time.delta()).just_finished() {
+                    *shots_left -= 1;
+                    fire(1.);
+                }
+                if *shots_left == 0 {
+                    state.burst = None;
+                }
+                continue;
+            }
+
+            match weapon.pattern {
+                FirePattern::Charge {
+                    charge_time,
+                    max_scale,
+                } => {
+                    if controls.shoot {
+                        if state.can_fire() {
+                            state.charge = Some(state.charge.unwrap_or(0.) + time.delta_seconds());
+                        }
+                    } else if let Some(charge) = state.charge.take() {
+                        // fire on release
+                        let charge = (charge / charge_time).min(1.);
+                        fire(1. + (max_scale - 1.) * charge);
+                        state.consume(weapon, cooldown_factor);
+                    }
+                }
+                FirePattern::Burst { count, interval } => {
+                    if controls.shoot && state.can_fire() {
+                        fire(1.);
+                        state.consume(weapon, cooldown_factor);
+                        if count > 1 {
+                            let timer = Timer::from_seconds(interval, TimerMode::Repeating);
+                            state.burst = Some((count - 1, timer));
+                        }
+                    }
+                }
+                FirePattern::Single | FirePattern::Spread { .. } => {
+                    if controls.shoot && state.can_fire() {
+                        fire(1.);
+                        state.consume(weapon, cooldown_factor);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Spawn the projectiles for a single shot of a weapon
+fn spawn_volley(
+    commands: &mut Commands,
+    weapon: &WeaponConfig,
+    xform: &Transform,
+    shooter_velocity: Vec2,
+    sprites: &AssetMap<Image>,
+    scale: f32,
+) {
+    let (count, angle) = match weapon.pattern {
+        FirePattern::Spread { count, angle } => (count, angle),
+        _ => (1, 0.),
+    };
+    let projectile_config = weapon.projectile.scaled(scale);
+    for i in 0..count {
+        let offset = (i as f32 - (count - 1) as f32 / 2.) * angle;
+        let shot_xform = xform.with_rotation(xform.rotation * Quat::from_rotation_z(offset));
+        commands.spawn(ProjectileBundle::from_config(
+            &projectile_config,
+            &shot_xform,
+            shooter_velocity,
+            sprites,
+        ));
+    }
+}